@@ -59,3 +59,138 @@
 
 (if true 1 -1)
 (if false 1 -1)
+
+; Functions close over the environment they were defined in.
+(define make-adder (lambda (n) (lambda (x) (+ x n))))
+(define add3 (make-adder 3))
+(add3 4)
+(define n 100)
+(add3 4)
+
+(define x 1)
+(define get-x (lambda () x))
+((lambda (x) (get-x)) 2)
+((lambda (x) ((lambda (x) x) (* x 10))) 2)
+
+(define count-down (lambda (n) (if (nil? n) 0 (count-down (cdr n)))))
+(count-down '(1 2 3))
+
+; Calls must not leak bindings into the caller's environment.
+((lambda (leaked) (define also-leaked leaked)) 5)
+leaked
+also-leaked
+
+#| Block comments #| can be nested |# and
+   span several lines. |#
+(+ 1 #;(car 5) 2) ; A datum comment skips the next expression.
+
+"Hello,\tworld!\n"
+(string-append "foo" "bar" "baz")
+(string-length "héllo")
+(substring "hello world" 6 11)
+(string-split "a,b,c" ",")
+(string-join '("a" "b" "c") ", ")
+(string-index-of "hello" "ll")
+(string-upcase "shout")
+(string->number "123")
+(symbol->string 'abc)
+
+#\a
+'(#\space #\newline #\x41)
+(char->integer #\A)
+(integer->char 955)
+(char<? #\a #\b #\c)
+(char-upcase #\z)
+(string->list "abc")
+(list->string '(#\h #\i))
+
+(* 2147483647 2) ; Integers are 64 bits wide.
+(+ 9223372036854775807 1) ; Overflowing promotes to a bignum,
+(- (* 9223372036854775807 2) 9223372036854775807) ; and back again.
+(* 123456789012345678901234567890 987654321098765432109876543210)
+
+(+ 1 2.5) ; Mixing integers and floats gives a float.
+(/ 6 3)
+(/ 1 3)
+'(1e3 -0.5 +inf.0 +nan.0)
+(integer? 2.0)
+(inexact->exact 4.0)
+(+ 1/3 2/3) ; Rationals are exact and collapse to integers when possible.
+(numerator 6/4)
+(inexact->exact 0.5)
+
+(quotient -7 2)
+(remainder -7 2)
+(modulo -7 2)
+(gcd 12 18)
+(lcm 4 6)
+(expt 2 100)
+(expt 2/3 -2)
+(exact-integer-sqrt 17) ; The root and what is left over.
+(arithmetic-shift 1 10)
+(bit-xor 12 10)
+
+(< 1 2 3) ; Comparisons are chained.
+(= 1 1.0 2/2)
+(define factorial (lambda (n) (if (zero? n) 1 (* n (factorial (- n 1))))))
+(factorial 30)
+(even? 10)
+
+(eq? 'a 'a)
+(eq? "abc" "abc") ; Different strings,
+(equal? "abc" "abc") ; with the same contents.
+(eqv? 2 2.0)
+(equal? '(1 (2 3) . 4) '(1 (2 3) . 4))
+
+(define (square x) (* x x)) ; Shorthand for defining functions.
+(square 12)
+(define (sum-of-squares a b)
+  (define a2 (square a)) ; Internal definitions stay inside the body.
+  (define b2 (square b))
+  (+ a2 b2))
+(sum-of-squares 3 4)
+a2
+(begin (display "side ") (display "effects") (newline) 'done)
+
+(let ((x 1) (y 2)) (+ x y))
+(let* ((x 1) (y (* x 10))) y)
+(letrec ((my-even? (lambda (n) (if (zero? n) true (my-odd? (- n 1)))))
+         (my-odd? (lambda (n) (if (zero? n) false (my-even? (- n 1))))))
+  (my-even? 10))
+(let loop ((i 0) (acc '()))
+  (if (= i 5) acc (loop (+ i 1) (cons i acc))))
+
+(define (classify n)
+  (cond ((< n 0) 'negative)
+        ((= n 0) 'zero)
+        (else 'positive)))
+(classify -3)
+(cond ((* 2 3) => (lambda (x) (+ x 1))))
+(case (* 2 3)
+  ((2 3 5 7) 'prime)
+  ((1 4 6 8 9) 'composite)
+  (else 'other))
+(when (> 3 2) (display "when ran") (newline))
+(unless (> 3 2) (display "unless ran") (newline))
+
+; Calls in tail position do not grow the stack.
+(define (count-up i n) (if (< i n) (count-up (+ i 1) n) i))
+(count-up 0 100000)
+
+; Other calls are only limited by the maximum depth.
+(define (build-list n) (if (= n 0) '() (cons n (build-list (- n 1)))))
+(car (build-list 50000))
+(define (forever n) (+ 1 (forever n)))
+(forever 0)
+
+(define counter 0)
+(define (increment!) (set! counter (+ counter 1)))
+(increment!)
+(increment!)
+counter
+(define (make-account balance)
+  (lambda (amount) (set! balance (+ balance amount)) balance))
+(define account (make-account 100))
+(account 10)
+(account -30)
+(set! unbound-variable 1)
@@ -0,0 +1,59 @@
+use anyhow::{bail, Context, Result};
+use std::{ffi::OsString, path::PathBuf};
+
+pub const USAGE: &str = "\
+Usage: rust_lisp [OPTIONS] [FILE | -]... [-- ARGS...]
+
+Evaluates each FILE in order, or starts an interactive REPL if there is
+nothing to evaluate. A FILE of - reads the program from standard input.
+Everything after -- is made available to the program as the list *args*.
+
+Options:
+  -e, --eval EXPR  Evaluate EXPR and print the results
+  -q, --quiet      Only print explicit output and errors
+  -h, --help       Print this help message";
+
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    Expression(String),
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub sources: Vec<Source>,
+    pub script_args: Vec<OsString>,
+    pub quiet: bool,
+    pub help: bool,
+}
+
+impl Options {
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("--") => {
+                    options.script_args.extend(args);
+                    break;
+                }
+                Some("-e" | "--eval") => {
+                    let expr = args.next().context("-e requires an expression")?;
+                    let expr = expr
+                        .into_string()
+                        .ok()
+                        .context("expression passed to -e must be valid UTF-8")?;
+                    options.sources.push(Source::Expression(expr));
+                }
+                Some("-q" | "--quiet") => options.quiet = true,
+                Some("-h" | "--help") => options.help = true,
+                Some("-") => options.sources.push(Source::Stdin),
+                Some(flag) if flag.starts_with('-') => bail!("unknown option {flag}"),
+                _ => options.sources.push(Source::File(PathBuf::from(arg))),
+            }
+        }
+
+        Ok(options)
+    }
+}
@@ -1,84 +1,572 @@
-use crate::types::*;
-use anyhow::{anyhow, ensure, Error, Result};
+use crate::types::{Cons, Environment, Evaluation, Object, Rational};
+use anyhow::{anyhow, bail, ensure, Error, Result};
 use itertools::Itertools;
-use std::rc::Rc;
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use std::{cmp::Ordering, iter, rc::Rc, vec};
 
-pub fn add(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
-    match (lhs_obj, rhs_obj) {
-        (Object::Integer(lhs), Object::Integer(rhs)) => Ok(Object::Integer(lhs + rhs)),
-        _ => Err(make_type_error("add", &[lhs_obj, rhs_obj])),
+/// Turns `value` into a machine integer if it fits, so that every integer
+/// has exactly one representation.
+pub fn normalize_integer(value: BigInt) -> Object {
+    i64::try_from(&value).map_or_else(|_| Object::BigInteger(value), Object::Integer)
+}
+
+fn to_bigint(obj: &Object) -> Option<BigInt> {
+    match obj {
+        Object::Integer(inner) => Some((*inner).into()),
+        Object::BigInteger(inner) => Some(inner.clone()),
+        _ => None,
     }
 }
 
-pub fn sub(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+/// A numerator and a positive denominator, not necessarily in lowest terms.
+type Fraction = (BigInt, BigInt);
+
+fn to_fraction(obj: &Object) -> Option<Fraction> {
+    match obj {
+        Object::Rational(inner) => Some((inner.numerator().clone(), inner.denominator().clone())),
+        _ => to_bigint(obj).map(|integer| (integer, BigInt::one())),
+    }
+}
+
+/// Reduces a fraction to lowest terms, giving an integer if the denominator
+/// ends up being 1.
+pub fn make_rational(numerator: BigInt, denominator: BigInt) -> Result<Object> {
+    ensure!(!denominator.is_zero(), "division by zero");
+    let divisor = numerator.gcd(&denominator) * denominator.signum();
+    let numerator = numerator / &divisor;
+    let denominator = denominator / divisor;
+    Ok(if denominator.is_one() {
+        normalize_integer(numerator)
+    } else {
+        Object::Rational(Rational::new_unchecked(numerator, denominator))
+    })
+}
+
+fn to_f64(obj: &Object) -> Option<f64> {
+    match obj {
+        Object::Integer(inner) => inner.to_f64(),
+        Object::BigInteger(inner) => inner.to_f64(),
+        Object::Rational(inner) => Some(inner.to_f64()),
+        Object::Float(inner) => Some(*inner),
+        _ => None,
+    }
+}
+
+/// The exact value of a finite float.
+fn float_to_fraction(value: f64) -> Option<Fraction> {
+    if !value.is_finite() {
+        return None;
+    }
+    let bits = value.to_bits();
+    let biased_exponent = (bits >> 52) & 0x7ff;
+    let fraction_bits = bits & ((1 << 52) - 1);
+    let (mantissa, exponent) = if biased_exponent == 0 {
+        (fraction_bits, -1074)
+    } else {
+        (
+            fraction_bits | (1 << 52),
+            i64::from_u64(biased_exponent)? - 1075,
+        )
+    };
+    let mantissa = BigInt::from(mantissa) * if value < 0.0 { -1 } else { 1 };
+    let shift = usize::try_from(exponent.unsigned_abs()).ok()?;
+    Some(if exponent >= 0 {
+        (mantissa << shift, BigInt::one())
+    } else {
+        (mantissa, BigInt::one() << shift)
+    })
+}
+
+pub const fn is_number(obj: &Object) -> bool {
+    matches!(
+        obj,
+        Object::Integer(_) | Object::BigInteger(_) | Object::Rational(_) | Object::Float(_)
+    )
+}
+
+/// Applies `small` to machine integers, falling back to `exact` if either
+/// operand is a bignum or a rational or if `small` overflows. If either
+/// operand is a float, both are converted to floats and `float` is used
+/// instead.
+fn arithmetic_op(
+    func_name: &str,
+    lhs_obj: &Object,
+    rhs_obj: &Object,
+    small: fn(i64, i64) -> Option<i64>,
+    exact: fn(Fraction, Fraction) -> Fraction,
+    float: fn(f64, f64) -> f64,
+) -> Result<Object> {
     match (lhs_obj, rhs_obj) {
-        (Object::Integer(lhs), Object::Integer(rhs)) => Ok(Object::Integer(lhs - rhs)),
-        _ => Err(make_type_error("sub", &[lhs_obj, rhs_obj])),
+        (Object::Integer(lhs), Object::Integer(rhs)) => {
+            if let Some(result) = small(*lhs, *rhs) {
+                return Ok(Object::Integer(result));
+            }
+        }
+        (Object::Float(_), _) | (_, Object::Float(_)) => {
+            if let (Some(lhs), Some(rhs)) = (to_f64(lhs_obj), to_f64(rhs_obj)) {
+                return Ok(Object::Float(float(lhs, rhs)));
+            }
+        }
+        _ => {}
+    }
+    match (to_fraction(lhs_obj), to_fraction(rhs_obj)) {
+        (Some(lhs), Some(rhs)) => {
+            let (numerator, denominator) = exact(lhs, rhs);
+            make_rational(numerator, denominator)
+        }
+        _ => Err(make_type_error(func_name, &[lhs_obj, rhs_obj])),
     }
 }
 
+pub fn add(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    arithmetic_op(
+        "add",
+        lhs_obj,
+        rhs_obj,
+        i64::checked_add,
+        |(a, b), (c, d)| (a * &d + c * &b, b * d),
+        |lhs, rhs| lhs + rhs,
+    )
+}
+
+pub fn sub(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    arithmetic_op(
+        "sub",
+        lhs_obj,
+        rhs_obj,
+        i64::checked_sub,
+        |(a, b), (c, d)| (a * &d - c * &b, b * d),
+        |lhs, rhs| lhs - rhs,
+    )
+}
+
 pub fn mul(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    arithmetic_op(
+        "mul",
+        lhs_obj,
+        rhs_obj,
+        i64::checked_mul,
+        |(a, b), (c, d)| (a * c, b * d),
+        |lhs, rhs| lhs * rhs,
+    )
+}
+
+/// Divides exactly unless either operand is a float. Exact division by zero
+/// is an error.
+pub fn div(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    arithmetic_op(
+        "div",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| {
+            let quotient = lhs.checked_div(rhs)?;
+            (quotient.checked_mul(rhs)? == lhs).then_some(quotient)
+        },
+        |(a, b), (c, d)| (a * d, b * c),
+        |lhs, rhs| lhs / rhs,
+    )
+}
+
+/// Compares two numbers, returning `None` if either of them is NaN.
+pub fn compare_numbers(lhs_obj: &Object, rhs_obj: &Object) -> Result<Option<Ordering>> {
     match (lhs_obj, rhs_obj) {
-        (Object::Integer(lhs), Object::Integer(rhs)) => Ok(Object::Integer(lhs * rhs)),
-        _ => Err(make_type_error("mul", &[lhs_obj, rhs_obj])),
+        (Object::Integer(lhs), Object::Integer(rhs)) => Ok(Some(lhs.cmp(rhs))),
+        (Object::Float(_), _) | (_, Object::Float(_)) => match (to_f64(lhs_obj), to_f64(rhs_obj)) {
+            (Some(lhs), Some(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+            _ => Err(make_type_error("compare", &[lhs_obj, rhs_obj])),
+        },
+        _ => match (to_fraction(lhs_obj), to_fraction(rhs_obj)) {
+            (Some((a, b)), Some((c, d))) => Ok(Some((a * d).cmp(&(c * b)))),
+            _ => Err(make_type_error("compare", &[lhs_obj, rhs_obj])),
+        },
     }
 }
 
-fn join_two_lists_obj(first_obj: &Object, second_obj: &Object, last: &Cons) -> Cons {
-    match (first_obj, second_obj) {
-        (Object::Cons(first), Object::Cons(second)) => join_two_lists_cons(first, second, last),
-        _ => last.clone(),
+/// Whether `holds` is true for the ordering of every pair of adjacent
+/// numbers, as in `(< a b c)`. Stops comparing at the first pair for which it
+/// is not. Comparisons involving NaN are always false.
+pub fn compare_chain(
+    func_name: &str,
+    first: &Rc<Object>,
+    rest: &[Rc<Object>],
+    holds: fn(Ordering) -> bool,
+) -> Result<bool> {
+    if !is_number(first) {
+        return Err(make_type_error(func_name, &[first]));
     }
+    for (lhs, rhs) in iter::once(first).chain(rest).tuple_windows() {
+        let ordering =
+            compare_numbers(lhs, rhs).map_err(|_| make_type_error(func_name, &[lhs, rhs]))?;
+        if !ordering.is_some_and(holds) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
-pub fn join_two_lists_cons(first: &Cons, second: &Cons, last: &Cons) -> Cons {
-    match (&first.0, &second.0) {
-        (Some((first_car, first_cdr)), Some((second_car, second_cdr))) => Cons(Some((
-            Rc::new(Object::Cons(Cons(Some((
-                first_car.clone(),
-                second_car.clone(),
-            ))))),
-            Rc::new(Object::Cons(join_two_lists_obj(
-                first_cdr, second_cdr, last,
-            ))),
-        ))),
-        _ => last.clone(),
-    }
-}
-
-pub fn eval_list_elements(list: &Cons, env: &Cons) -> Result<(Cons, Cons)> {
-    match &list.0 {
-        None => Ok((list.clone(), env.clone())),
-        Some((first, second)) => {
-            let (evaluated_first, env) = first.clone().eval(env)?;
-            if let Object::Cons(rest) = &**second {
-                let (evaluated_rest, env) = eval_list_elements(rest, &env)?;
-                Ok((
-                    Cons(Some((
-                        evaluated_first,
-                        Rc::<Object>::new(Object::Cons(evaluated_rest)),
-                    ))),
-                    env,
-                ))
-            } else {
-                Ok((
-                    Cons(Some((evaluated_first, Rc::new(Object::Cons(Cons(None)))))),
-                    env,
-                ))
-            }
+/// Compares `obj` to zero, for `zero?` and similar predicates.
+pub fn sign_is(func_name: &str, obj: &Object, holds: fn(Ordering) -> bool) -> Result<bool> {
+    let ordering = compare_numbers(obj, &Object::Integer(0))
+        .map_err(|_| make_type_error(func_name, &[obj]))?;
+    Ok(ordering.is_some_and(holds))
+}
+
+pub fn is_even(func_name: &str, obj: &Object) -> Result<bool> {
+    match obj {
+        Object::Integer(inner) => Ok(inner % 2 == 0),
+        Object::BigInteger(inner) => Ok(inner.is_even()),
+        _ => Err(make_type_error(func_name, &[obj])),
+    }
+}
+
+/// Whether `obj` is a number with no fractional part, which includes floats
+/// such as `2.0`.
+pub fn is_integer(obj: &Object) -> bool {
+    match obj {
+        Object::Integer(_) | Object::BigInteger(_) => true,
+        Object::Float(inner) => inner.is_finite() && inner.fract() == 0.0,
+        _ => false,
+    }
+}
+
+pub fn exact_to_inexact(obj: &Object) -> Result<f64> {
+    to_f64(obj).ok_or_else(|| make_type_error("exact->inexact", &[obj]))
+}
+
+pub fn inexact_to_exact(obj: &Rc<Object>) -> Result<Rc<Object>> {
+    match &**obj {
+        Object::Float(inner) => {
+            let (numerator, denominator) = float_to_fraction(*inner)
+                .ok_or_else(|| anyhow!("inexact->exact: {obj} has no exact representation"))?;
+            Ok(Rc::new(make_rational(numerator, denominator)?))
         }
+        _ if is_number(obj) => Ok(obj.clone()),
+        _ => Err(make_type_error("inexact->exact", &[obj])),
+    }
+}
+
+pub fn numerator(obj: &Object) -> Result<Object> {
+    let (numerator, _) = to_fraction(obj).ok_or_else(|| make_type_error("numerator", &[obj]))?;
+    Ok(normalize_integer(numerator))
+}
+
+pub fn denominator(obj: &Object) -> Result<Object> {
+    let (_, denominator) =
+        to_fraction(obj).ok_or_else(|| make_type_error("denominator", &[obj]))?;
+    Ok(normalize_integer(denominator))
+}
+
+/// Like [`arithmetic_op`], but only for exact integers.
+fn integer_op(
+    func_name: &str,
+    lhs_obj: &Object,
+    rhs_obj: &Object,
+    small: fn(i64, i64) -> Option<i64>,
+    big: fn(BigInt, BigInt) -> Result<BigInt>,
+) -> Result<Object> {
+    if let (Object::Integer(lhs), Object::Integer(rhs)) = (lhs_obj, rhs_obj) {
+        if let Some(result) = small(*lhs, *rhs) {
+            return Ok(Object::Integer(result));
+        }
+    }
+    match (to_bigint(lhs_obj), to_bigint(rhs_obj)) {
+        (Some(lhs), Some(rhs)) => Ok(normalize_integer(big(lhs, rhs)?)),
+        _ => Err(make_type_error(func_name, &[lhs_obj, rhs_obj])),
     }
 }
 
-#[macro_export]
-macro_rules! make_list {
-    () => {
-        Cons(None)
+/// Division rounded towards zero.
+pub fn quotient(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "quotient",
+        lhs_obj,
+        rhs_obj,
+        i64::checked_div,
+        |lhs, rhs| {
+            ensure!(!rhs.is_zero(), "division by zero");
+            Ok(lhs / rhs)
+        },
+    )
+}
+
+/// The remainder of [`quotient`], which has the same sign as `lhs_obj`.
+pub fn remainder(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "remainder",
+        lhs_obj,
+        rhs_obj,
+        i64::checked_rem,
+        |lhs, rhs| {
+            ensure!(!rhs.is_zero(), "division by zero");
+            Ok(lhs % rhs)
+        },
+    )
+}
+
+/// The remainder of division rounded downwards, which has the same sign as
+/// `rhs_obj`.
+pub fn modulo(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "modulo",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| {
+            let remainder = lhs.checked_rem(rhs)?;
+            Some(if remainder != 0 && (remainder < 0) != (rhs < 0) {
+                remainder + rhs
+            } else {
+                remainder
+            })
+        },
+        |lhs, rhs| {
+            ensure!(!rhs.is_zero(), "division by zero");
+            Ok(lhs.mod_floor(&rhs))
+        },
+    )
+}
+
+pub fn gcd(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "gcd",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| i64::try_from(lhs.unsigned_abs().gcd(&rhs.unsigned_abs())).ok(),
+        |lhs, rhs| Ok(lhs.gcd(&rhs)),
+    )
+}
+
+pub fn lcm(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "lcm",
+        lhs_obj,
+        rhs_obj,
+        |_, _| None,
+        |lhs, rhs| Ok(lhs.lcm(&rhs)),
+    )
+}
+
+pub fn bit_and(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "bit-and",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| Some(lhs & rhs),
+        |lhs, rhs| Ok(lhs & rhs),
+    )
+}
+
+pub fn bit_or(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "bit-or",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| Some(lhs | rhs),
+        |lhs, rhs| Ok(lhs | rhs),
+    )
+}
+
+pub fn bit_xor(lhs_obj: &Object, rhs_obj: &Object) -> Result<Object> {
+    integer_op(
+        "bit-xor",
+        lhs_obj,
+        rhs_obj,
+        |lhs, rhs| Some(lhs ^ rhs),
+        |lhs, rhs| Ok(lhs ^ rhs),
+    )
+}
+
+/// The most bits that an exact integer produced by `arithmetic-shift` or
+/// `expt` may have, so that huge arguments give an error instead of using up
+/// all memory.
+const MAX_INTEGER_BITS: u64 = 1 << 24;
+
+/// Shifts `n` left by `shift` bits, or right if `shift` is negative.
+pub fn arithmetic_shift(n: BigInt, shift: i64) -> Result<Object> {
+    ensure!(
+        shift <= 0
+            || n.is_zero()
+            || n.bits().saturating_add(shift.unsigned_abs()) <= MAX_INTEGER_BITS,
+        "arithmetic-shift: result of shifting by {shift} is too large"
+    );
+    let amount = usize::try_from(shift.unsigned_abs())?;
+    Ok(normalize_integer(if shift >= 0 {
+        n << amount
+    } else {
+        n >> amount
+    }))
+}
+
+pub fn abs(obj: &Object) -> Result<Object> {
+    match obj {
+        Object::Integer(inner) => Ok(inner.checked_abs().map_or_else(
+            || normalize_integer(BigInt::from(*inner).abs()),
+            Object::Integer,
+        )),
+        Object::BigInteger(inner) => Ok(normalize_integer(inner.abs())),
+        Object::Rational(inner) => Ok(Object::Rational(Rational::new_unchecked(
+            inner.numerator().abs(),
+            inner.denominator().clone(),
+        ))),
+        Object::Float(inner) => Ok(Object::Float(inner.abs())),
+        _ => Err(make_type_error("abs", &[obj])),
+    }
+}
+
+/// Picks whichever of the two numbers `pick_lhs` chooses, converting the
+/// result to a float if either of them is one.
+fn pick_number(
+    func_name: &str,
+    lhs: &Rc<Object>,
+    rhs: &Rc<Object>,
+    pick_lhs: fn(Ordering) -> bool,
+) -> Result<Rc<Object>> {
+    let ordering =
+        compare_numbers(lhs, rhs).map_err(|_| make_type_error(func_name, &[lhs, rhs]))?;
+    let picked = match ordering {
+        Some(ordering) if pick_lhs(ordering) => lhs,
+        Some(_) => rhs,
+        None => return Ok(Rc::new(Object::Float(f64::NAN))),
     };
+    match (&**lhs, &**rhs) {
+        (Object::Float(_), _) | (_, Object::Float(_)) => {
+            Ok(Rc::new(Object::Float(exact_to_inexact(picked)?)))
+        }
+        _ => Ok(picked.clone()),
+    }
+}
+
+pub fn min(lhs: &Rc<Object>, rhs: &Rc<Object>) -> Result<Rc<Object>> {
+    pick_number("min", lhs, rhs, Ordering::is_le)
+}
+
+pub fn max(lhs: &Rc<Object>, rhs: &Rc<Object>) -> Result<Rc<Object>> {
+    pick_number("max", lhs, rhs, Ordering::is_ge)
+}
+
+/// Raises `base` to the power of `exponent`. The result is exact if `base` is
+/// exact and `exponent` is an exact integer.
+pub fn expt(base: &Object, exponent: &Object) -> Result<Object> {
+    if let (Some((numerator, denominator)), Some(exponent)) =
+        (to_fraction(base), to_bigint(exponent))
+    {
+        // Powers of 0, 1 and -1 only depend on the sign of the exponent and
+        // on whether it is even, so huge exponents are fine for them.
+        let exponent = if denominator.is_one() && numerator.abs() <= BigInt::one() {
+            exponent.signum() * BigInt::from(if exponent.is_even() { 2 } else { 1 })
+        } else {
+            exponent
+        };
+        let power = u32::try_from(exponent.magnitude())
+            .map_err(|_| anyhow!("expt: exponent {exponent} is too large"))?;
+        let bits = numerator.bits().max(denominator.bits());
+        ensure!(
+            bits.saturating_sub(1).saturating_mul(u64::from(power)) <= MAX_INTEGER_BITS,
+            "expt: result of raising to {exponent} is too large"
+        );
+        let (numerator, denominator) = (numerator.pow(power), denominator.pow(power));
+        return if exponent.is_negative() {
+            make_rational(denominator, numerator)
+        } else {
+            make_rational(numerator, denominator)
+        };
+    }
+    match (to_f64(base), to_f64(exponent)) {
+        (Some(base), Some(exponent)) => Ok(Object::Float(base.powf(exponent))),
+        _ => Err(make_type_error("expt", &[base, exponent])),
+    }
+}
 
-    ($first:expr $(, $rest:expr)*) => {
-        Cons(Some(($first, Rc::new(Object::Cons(make_list!($($rest),*))))))
+/// The largest integer whose square is at most `n`, along with how much
+/// smaller its square is than `n`.
+pub fn exact_integer_sqrt(n: &BigInt) -> Result<Cons> {
+    ensure!(!n.is_negative(), "exact-integer-sqrt: {n} is negative");
+    let root = n.sqrt();
+    let rest = n - &root * &root;
+    Ok([root, rest]
+        .into_iter()
+        .map(|part| Rc::new(normalize_integer(part)))
+        .collect())
+}
+
+/// Parses a decimal integer literal, using a bignum if it does not fit in an
+/// `i64`.
+pub fn parse_integer(string: &str) -> Option<Object> {
+    if let Ok(small) = string.parse() {
+        return Some(Object::Integer(small));
+    }
+    let digits = string.strip_prefix(['+', '-']).unwrap_or(string);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    string.parse().ok().map(normalize_integer)
+}
+
+/// Parses a rational literal such as `-1/3`, reducing it to lowest terms.
+fn parse_rational(string: &str) -> Option<Result<Object>> {
+    let (numerator, denominator) = string.split_once('/')?;
+    if denominator.starts_with(['+', '-']) {
+        return None;
+    }
+    let numerator = to_bigint(&parse_integer(numerator)?)?;
+    let denominator = to_bigint(&parse_integer(denominator)?)?;
+    Some(make_rational(numerator, denominator))
+}
+
+/// Parses a float literal such as `1.5`, `-2e10` or `+inf.0`. Rust's own
+/// spellings of infinity and NaN are not accepted since those are valid
+/// symbols.
+pub fn parse_float(string: &str) -> Option<f64> {
+    match string {
+        "+inf.0" => return Some(f64::INFINITY),
+        "-inf.0" => return Some(f64::NEG_INFINITY),
+        "+nan.0" | "-nan.0" => return Some(f64::NAN),
+        _ => {}
+    }
+    let is_float_char = |c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E');
+    if !string.chars().all(is_float_char) || !string.contains(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    string.parse().ok()
+}
+
+/// Parses a number literal, or gives `None` if `string` is not one. A
+/// rational literal whose denominator is zero is an error.
+pub fn parse_number(string: &str) -> Option<Result<Object>> {
+    parse_integer(string)
+        .map(Ok)
+        .or_else(|| parse_rational(string))
+        .or_else(|| parse_float(string).map(|value| Ok(Object::Float(value))))
+}
+
+/// Evaluates each expression of `body` in order. The last expression is left
+/// for the caller since it is in tail position.
+///
+/// Definitions go into the innermost frame of `env`, which the caller creates
+/// for the body, so they can all refer to each other.
+pub fn eval_body(body: &Cons, env: &Environment) -> Result<Evaluation> {
+    fn eval_rest(
+        mut init: vec::IntoIter<Rc<Object>>,
+        last: Rc<Object>,
+        env: Environment,
+    ) -> Evaluation {
+        match init.next() {
+            None => Evaluation::tail_call(last, &env),
+            Some(expr) => Evaluation::then(expr, &env.clone(), move |_, _| {
+                Ok(eval_rest(init, last, env))
+            }),
+        }
+    }
+
+    let mut exprs = body.iter().collect::<Vec<_>>();
+    let Some(last) = exprs.pop() else {
+        return Ok(Evaluation::Done(
+            Rc::new(Object::Cons(Cons(None))),
+            env.clone(),
+        ));
     };
+    Ok(eval_rest(exprs.into_iter(), last, env.clone()))
 }
 
 pub fn make_type_error(func_name: &str, args: &[&Object]) -> Error {
@@ -106,17 +594,33 @@ pub fn ensure_n_args(func_name: &str, n: usize, list: &Cons) -> Result<()> {
     Ok(())
 }
 
+pub fn ensure_at_least_n_args(func_name: &str, n: usize, list: &Cons) -> Result<()> {
+    ensure!(
+        list.is_proper_list(),
+        "call to {func_name} must be a proper list",
+    );
+
+    let length = list.len();
+    ensure!(
+        length >= n,
+        "{func_name} expected at least {n} arguments but got {length}"
+    );
+
+    Ok(())
+}
+
 pub fn int_to_bool(obj: &Object) -> Result<Rc<Object>> {
     match obj {
         Object::Integer(val) => Ok(Rc::new(Object::Bool(*val != 0))),
-        _ => Err(make_type_error("int_to_bool", &[obj])),
+        Object::BigInteger(_) => Ok(Rc::new(Object::Bool(true))),
+        _ => Err(make_type_error("int->bool", &[obj])),
     }
 }
 
 pub fn bool_to_int(obj: &Object) -> Result<Rc<Object>> {
     match obj {
         Object::Bool(val) => Ok(Rc::new(Object::Integer((*val).into()))),
-        _ => Err(make_type_error("bool_to_int", &[obj])),
+        _ => Err(make_type_error("bool->int", &[obj])),
     }
 }
 
@@ -130,3 +634,127 @@ pub const fn is_truthy(obj: &Object) -> bool {
 pub fn not(obj: &Object) -> Rc<Object> {
     Rc::new(Object::Bool(!is_truthy(obj)))
 }
+
+fn string_index(func_name: &str, string: &str, index: i64) -> Result<usize> {
+    let length = string.chars().count();
+    match usize::try_from(index) {
+        Ok(index) if index <= length => Ok(index),
+        _ => bail!("{func_name}: index {index} is out of range for a string of length {length}"),
+    }
+}
+
+fn char_to_byte_index(string: &str, index: usize) -> usize {
+    string
+        .char_indices()
+        .nth(index)
+        .map_or(string.len(), |(byte_index, _)| byte_index)
+}
+
+pub fn string_length(string: &str) -> Result<i64> {
+    Ok(i64::try_from(string.chars().count())?)
+}
+
+pub fn substring(string: &str, start: i64, end: i64) -> Result<String> {
+    let start = string_index("substring", string, start)?;
+    let end = string_index("substring", string, end)?;
+    ensure!(
+        start <= end,
+        "substring: start index {start} is greater than end index {end}"
+    );
+    let start = char_to_byte_index(string, start);
+    let end = char_to_byte_index(string, end);
+    Ok(string[start..end].to_owned())
+}
+
+pub fn string_index_of(string: &str, needle: &str) -> Result<Object> {
+    match string.find(needle) {
+        Some(byte_index) => Ok(Object::Integer(string_length(&string[..byte_index])?)),
+        None => Ok(Object::Bool(false)),
+    }
+}
+
+pub fn string_split(string: &str, separator: &str) -> Result<Cons> {
+    ensure!(
+        !separator.is_empty(),
+        "string-split: separator must not be empty"
+    );
+    Ok(string
+        .split(separator)
+        .map(|part| Rc::new(Object::from(part)))
+        .collect())
+}
+
+pub fn string_join(strings: &Cons, separator: &str) -> Result<String> {
+    ensure!(
+        strings.is_proper_list(),
+        "string-join: first argument must be a proper list"
+    );
+    strings
+        .iter()
+        .map(|obj| match &*obj {
+            Object::String(string) => Ok(string.clone()),
+            _ => Err(make_type_error("string-join", &[&obj])),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|strings| strings.join(separator))
+}
+
+pub fn string_to_number(string: &str) -> Object {
+    parse_number(string)
+        .and_then(Result::ok)
+        .unwrap_or(Object::Bool(false))
+}
+
+/// Identity comparison, as used by `eq?`. Symbols, booleans and the empty
+/// list are not interned, so they are compared by value instead.
+pub fn is_eq(lhs: &Rc<Object>, rhs: &Rc<Object>) -> bool {
+    Rc::ptr_eq(lhs, rhs)
+        || match (&**lhs, &**rhs) {
+            (Object::Symbol(_), Object::Symbol(_))
+            | (Object::Bool(_), Object::Bool(_))
+            | (Object::Cons(Cons(None)), Object::Cons(Cons(None))) => lhs == rhs,
+            _ => false,
+        }
+}
+
+/// Like [`is_eq`], but numbers and characters are also compared by value.
+pub fn is_eqv(lhs: &Rc<Object>, rhs: &Rc<Object>) -> bool {
+    is_eq(lhs, rhs)
+        || match (&**lhs, &**rhs) {
+            (Object::Integer(_), Object::Integer(_))
+            | (Object::BigInteger(_), Object::BigInteger(_))
+            | (Object::Rational(_), Object::Rational(_))
+            | (Object::Float(_), Object::Float(_))
+            | (Object::Char(_), Object::Char(_)) => lhs == rhs,
+            _ => false,
+        }
+}
+
+/// Whether `holds` is true for every pair of adjacent elements, as in
+/// `(char<? a b c)`.
+pub fn is_chained<T: Copy>(first: T, rest: &[T], holds: impl Fn(T, T) -> bool) -> bool {
+    iter::once(first)
+        .chain(rest.iter().copied())
+        .tuple_windows()
+        .all(|(lhs, rhs)| holds(lhs, rhs))
+}
+
+pub fn integer_to_char(code_point: i64) -> Result<char> {
+    u32::try_from(code_point)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or_else(|| anyhow!("integer->char: {code_point} is not a valid code point"))
+}
+
+pub fn list_to_string(list: &Cons) -> Result<String> {
+    ensure!(
+        list.is_proper_list(),
+        "list->string: argument must be a proper list"
+    );
+    list.iter()
+        .map(|obj| match &*obj {
+            Object::Char(c) => Ok(*c),
+            _ => Err(make_type_error("list->string", &[&obj])),
+        })
+        .collect()
+}
@@ -0,0 +1,308 @@
+use crate::{
+    lexer::lex,
+    native::NativeFunction,
+    parser::{parse_expressions, ParseErrors},
+    span::{EvalError, SourceLocation, SpanTable},
+    types::{
+        with_max_depth, BuiltinFunction, Cons, Environment, Object, Quote, Symbol,
+        DEFAULT_MAX_DEPTH,
+    },
+    wrapped,
+};
+use anyhow::{anyhow, Context, Result};
+use std::{fs, path::Path, rc::Rc, sync::Arc};
+
+macro_rules! make_env {
+    (
+        $($builtin_name:literal => $func:expr),*;
+        $($name:literal = $value:expr),*
+    ) => {
+        {
+            let env = Environment::new();
+            $(
+                env.define(
+                    $builtin_name.parse().unwrap(),
+                    Rc::new(Object::BuiltinFunction(BuiltinFunction::named($builtin_name, $func))),
+                );
+            )*
+            $(
+                env.define($name.parse().unwrap(), $value);
+            )*
+            env
+        }
+    }
+}
+
+fn make_global_env() -> Environment {
+    make_env![
+        "car" => wrapped::car,
+        "cdr" => wrapped::cdr,
+        "cons" => wrapped::cons,
+        "lambda" => wrapped::lambda,
+        "+" => wrapped::add,
+        "-" => wrapped::sub,
+        "*" => wrapped::mul,
+        "/" => wrapped::div,
+        "=" => wrapped::num_eq,
+        "<" => wrapped::num_lt,
+        ">" => wrapped::num_gt,
+        "<=" => wrapped::num_le,
+        ">=" => wrapped::num_ge,
+        "zero?" => wrapped::is_zero,
+        "positive?" => wrapped::is_positive,
+        "negative?" => wrapped::is_negative,
+        "even?" => wrapped::is_even,
+        "odd?" => wrapped::is_odd,
+        "quotient" => wrapped::quotient,
+        "remainder" => wrapped::remainder,
+        "modulo" => wrapped::modulo,
+        "abs" => wrapped::abs,
+        "min" => wrapped::min,
+        "max" => wrapped::max,
+        "gcd" => wrapped::gcd,
+        "lcm" => wrapped::lcm,
+        "expt" => wrapped::expt,
+        "exact-integer-sqrt" => wrapped::exact_integer_sqrt,
+        "bit-and" => wrapped::bit_and,
+        "bit-or" => wrapped::bit_or,
+        "bit-xor" => wrapped::bit_xor,
+        "arithmetic-shift" => wrapped::arithmetic_shift,
+        "quote" => wrapped::quote,
+        "int->bool" => wrapped::int_to_bool,
+        "bool->int" => wrapped::bool_to_int,
+        "and" => wrapped::and,
+        "or" => wrapped::or,
+        "not" => wrapped::not,
+        "define" => wrapped::define,
+        "set!" => wrapped::set,
+        "begin" => wrapped::begin,
+        "let" => wrapped::r#let,
+        "let*" => wrapped::let_star,
+        "letrec" => wrapped::letrec,
+        "letrec*" => wrapped::letrec,
+        "nil?" => wrapped::is_nil,
+        "eq?" => wrapped::is_eq,
+        "eqv?" => wrapped::is_eqv,
+        "equal?" => wrapped::is_equal,
+        "int?" => wrapped::is_int,
+        "bool?" => wrapped::is_bool,
+        "number?" => wrapped::is_number,
+        "integer?" => wrapped::is_integer,
+        "float?" => wrapped::is_float,
+        "rational?" => wrapped::is_rational,
+        "numerator" => wrapped::numerator,
+        "denominator" => wrapped::denominator,
+        "exact->inexact" => wrapped::exact_to_inexact,
+        "inexact->exact" => wrapped::inexact_to_exact,
+        "if" => wrapped::r#if,
+        "cond" => wrapped::cond,
+        "case" => wrapped::case,
+        "when" => wrapped::when,
+        "unless" => wrapped::unless,
+        "display" => wrapped::display,
+        "newline" => wrapped::newline,
+        "string?" => wrapped::is_string,
+        "string-length" => wrapped::string_length,
+        "substring" => wrapped::substring,
+        "string-append" => wrapped::string_append,
+        "string-split" => wrapped::string_split,
+        "string-join" => wrapped::string_join,
+        "string-index-of" => wrapped::string_index_of,
+        "string-upcase" => wrapped::string_upcase,
+        "string-downcase" => wrapped::string_downcase,
+        "string->number" => wrapped::string_to_number,
+        "number->string" => wrapped::number_to_string,
+        "string->symbol" => wrapped::string_to_symbol,
+        "symbol->string" => wrapped::symbol_to_string,
+        "char?" => wrapped::is_char,
+        "char-alphabetic?" => wrapped::is_char_alphabetic,
+        "char-numeric?" => wrapped::is_char_numeric,
+        "char-whitespace?" => wrapped::is_char_whitespace,
+        "char-upcase" => wrapped::char_upcase,
+        "char-downcase" => wrapped::char_downcase,
+        "char->integer" => wrapped::char_to_integer,
+        "integer->char" => wrapped::integer_to_char,
+        "char=?" => wrapped::char_eq,
+        "char<?" => wrapped::char_lt,
+        "char>?" => wrapped::char_gt,
+        "char<=?" => wrapped::char_le,
+        "char>=?" => wrapped::char_ge,
+        "string->list" => wrapped::string_to_list,
+        "list->string" => wrapped::list_to_string;
+        "true" = Rc::new(Object::Bool(true)),
+        "false" = Rc::new(Object::Bool(false))
+    ]
+}
+
+/// Owns a global environment that expressions are evaluated in, so that
+/// definitions made by one call are visible to the next.
+pub struct Interpreter {
+    env: Environment,
+    spans: SpanTable,
+    max_depth: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    /// Creates an interpreter whose environment contains all builtins.
+    pub fn new() -> Self {
+        Self {
+            env: make_global_env(),
+            spans: SpanTable::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Limits how many evaluations that are not in tail position can be
+    /// nested, such as calls to a function that recurses without a tail
+    /// call. Evaluating more deeply nested expressions fails with "stack
+    /// depth exceeded".
+    pub const fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub const fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    /// Parses `source` into expressions without evaluating them. Errors that
+    /// happen while evaluating the expressions later will point back to
+    /// where in `source_name` they came from.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `source` is not valid syntax.
+    pub fn parse(&mut self, source_name: &str, source: &str) -> Result<Vec<Rc<Object>>> {
+        let lexed = lex(source);
+        let (exprs, spans) = parse_expressions(&lexed)
+            .map_err(|errors| ParseErrors::new(source_name, source, errors))?;
+        self.spans.prune();
+        let file = Arc::<str>::from(source_name);
+        for (obj, span) in spans {
+            let file = file.clone();
+            self.spans.insert(&obj, SourceLocation { file, span });
+        }
+        Ok(exprs)
+    }
+
+    /// Evaluates a single expression, keeping any definitions it makes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if evaluating the expression fails.
+    pub fn eval(&mut self, expr: &Rc<Object>) -> Result<Rc<Object>> {
+        // Definitions change the global frame in place, so the environment
+        // that evaluating results in is not needed.
+        let (result, _) = with_max_depth(self.max_depth, || expr.clone().eval(&self.env))
+            .map_err(|err| EvalError::locate(err, &self.spans))?;
+        Ok(result)
+    }
+
+    /// Evaluates every expression in `source` and returns the result of the
+    /// last one, or nil if there were none.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `source` can not be parsed or if evaluating any of the
+    /// expressions fails, in which case the remaining ones are skipped.
+    pub fn eval_str(&mut self, source: &str) -> Result<Rc<Object>> {
+        self.eval_source("<string>", source)
+    }
+
+    fn eval_source(&mut self, source_name: &str, source: &str) -> Result<Rc<Object>> {
+        self.parse(source_name, source)?
+            .into_iter()
+            .try_fold(Rc::new(Object::Cons(Cons(None))), |_, expr| {
+                self.eval(&expr)
+            })
+    }
+
+    /// Reads the file at `path` and evaluates it like [`Self::eval_str`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can not be read or if [`Self::eval_str`] fails.
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Rc<Object>> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read source file {}", path.display()))?;
+        self.eval_source(&path.display().to_string(), &source)
+    }
+
+    /// Binds `name` to `value` in the global environment, replacing any
+    /// previous binding.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is not a valid symbol.
+    pub fn define_global(&mut self, name: &str, value: impl Into<Rc<Object>>) -> Result<()> {
+        let name: Symbol = name
+            .parse()
+            .map_err(|()| anyhow!("{name} is not a valid symbol"))?;
+        self.env.define(name, value.into());
+        Ok(())
+    }
+
+    /// Binds `name` to a builtin backed by `func`, which may capture state
+    /// from the host program.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is not a valid symbol.
+    pub fn define_builtin(
+        &mut self,
+        name: &str,
+        func: impl Fn(&Cons, &Environment) -> Result<Rc<Object>> + 'static,
+    ) -> Result<()> {
+        let builtin =
+            BuiltinFunction::new(name, move |args, env| Ok((func(args, env)?, env.clone())));
+        self.define_global(name, Object::BuiltinFunction(builtin))
+    }
+
+    /// Binds `name` to a builtin with ordinary Rust parameters, see
+    /// [`BuiltinFunction::from_native`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is not a valid symbol.
+    pub fn define_native<Args: 'static>(
+        &mut self,
+        name: &str,
+        func: impl NativeFunction<Args> + 'static,
+    ) -> Result<()> {
+        self.define_global(
+            name,
+            Object::BuiltinFunction(BuiltinFunction::from_native(name, func)),
+        )
+    }
+
+    /// Calls the function bound to `name` with already evaluated arguments.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is unbound, is not bound to a function or if the
+    /// function itself fails.
+    pub fn call_function<T: Into<Rc<Object>>>(
+        &self,
+        name: &str,
+        args: impl IntoIterator<Item = T>,
+    ) -> Result<Rc<Object>> {
+        let name: Symbol = name
+            .parse()
+            .map_err(|()| anyhow!("{name} is not a valid symbol"))?;
+        let (func, _) = name.eval(&self.env)?;
+        // Quoting the arguments keeps the function from evaluating them again.
+        let args = args
+            .into_iter()
+            .map(|arg| Rc::new(Object::Quote(Quote(arg.into()))))
+            .collect::<Cons>();
+        let (result, _) = with_max_depth(self.max_depth, || func.apply(&args, &self.env))
+            .map_err(|err| EvalError::locate(err, &self.spans))?;
+        Ok(result)
+    }
+}
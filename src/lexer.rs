@@ -1,35 +1,239 @@
+use crate::{parser::ParseErrorKind, span::Span};
+
 #[derive(Clone, PartialEq, Eq, Debug)]
-pub enum Token {
+pub enum TokenKind {
     LParen,
     RParen,
     Quote,
+    DatumComment,
     Ident(String),
+    String(String),
+    Char(char),
+    /// Something that could not be lexed, such as an unterminated string.
+    /// Reported by the parser so that it can be collected with other errors.
+    Malformed(ParseErrorKind),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Keeps track of the line and column while moving forward through the
+/// source code.
+struct Position<'a> {
+    source: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Position<'a> {
+    const fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Moves forward to where `rest` starts, which must be a suffix of the
+    /// source code.
+    fn advance_to(&mut self, rest: &str) {
+        let offset = self.source.len() - rest.len();
+        for c in self.source[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset = offset;
+    }
+
+    /// The span from the current position to where `rest` starts.
+    const fn span_to(&self, rest: &str) -> Span {
+        Span {
+            start: self.offset,
+            end: self.source.len() - rest.len(),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+/// Skips a block comment whose opening `#|` has already been consumed.
+/// Block comments nest. Returns `None` if the comment is never closed.
+fn skip_block_comment(mut source: &str) -> Option<&str> {
+    let mut depth = 1_usize;
+    while depth > 0 {
+        let index = source.find(['#', '|'])?;
+        source = &source[index..];
+        source = if let Some(s) = source.strip_prefix("#|") {
+            depth += 1;
+            s
+        } else if let Some(s) = source.strip_prefix("|#") {
+            depth -= 1;
+            s
+        } else {
+            &source[1..]
+        };
+    }
+    Some(source)
+}
+
+const fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '\'' | ';' | '"')
+}
+
+/// Lexes a character literal whose opening `#\` has already been consumed.
+/// The character itself can also be a delimiter, as in `#\(`.
+fn lex_char(source: &str) -> (TokenKind, &str) {
+    let mut chars = source.chars();
+    let Some(first) = chars.next() else {
+        return (
+            TokenKind::Malformed(ParseErrorKind::InvalidCharacter(String::new())),
+            source,
+        );
+    };
+    let rest = chars.as_str();
+    let name_end = first.len_utf8() + rest.find(is_delimiter).unwrap_or(rest.len());
+    let (name, rest) = source.split_at(name_end);
+
+    let c = match name {
+        _ if name.len() == first.len_utf8() => Some(first),
+        "space" => Some(' '),
+        "newline" => Some('\n'),
+        "tab" => Some('\t'),
+        "return" => Some('\r'),
+        "null" | "nul" => Some('\0'),
+        "alarm" => Some('\x07'),
+        "backspace" => Some('\x08'),
+        "escape" => Some('\x1b'),
+        "delete" => Some('\x7f'),
+        _ => name
+            .strip_prefix('x')
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32),
+    };
+    let kind = c.map_or_else(
+        || TokenKind::Malformed(ParseErrorKind::InvalidCharacter(name.to_owned())),
+        TokenKind::Char,
+    );
+    (kind, rest)
+}
+
+/// Parses what comes after a `\` in a string literal.
+fn lex_escape(source: &str) -> Result<(char, &str), ParseErrorKind> {
+    let mut chars = source.chars();
+    let c = match chars.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('0') => '\0',
+        Some('"') => '"',
+        Some('\\') => '\\',
+        Some('u') => {
+            let invalid = || ParseErrorKind::InvalidEscape(String::from("\\u"));
+            let hex = chars.as_str().strip_prefix('{').ok_or_else(invalid)?;
+            let (digits, rest) = hex.split_once('}').ok_or_else(invalid)?;
+            let c = u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or_else(|| ParseErrorKind::InvalidEscape(format!("\\u{{{digits}}}")))?;
+            return Ok((c, rest));
+        }
+        Some(other) => return Err(ParseErrorKind::InvalidEscape(format!("\\{other}"))),
+        None => return Err(ParseErrorKind::UnterminatedString),
+    };
+    Ok((c, chars.as_str()))
+}
+
+/// Lexes a string literal whose opening `"` has already been consumed. After
+/// an invalid escape sequence the rest of the literal is still skipped so
+/// that lexing can continue after it.
+fn lex_string(mut source: &str) -> (TokenKind, &str) {
+    let mut string = String::new();
+    let mut error = None;
+
+    loop {
+        let Some(end) = source.find(['"', '\\']) else {
+            return (TokenKind::Malformed(ParseErrorKind::UnterminatedString), "");
+        };
+        string.push_str(&source[..end]);
+        source = &source[end..];
+        if let Some(rest) = source.strip_prefix('"') {
+            source = rest;
+            break;
+        }
+        source = match lex_escape(&source[1..]) {
+            Ok((c, rest)) => {
+                string.push(c);
+                rest
+            }
+            Err(ParseErrorKind::UnterminatedString) => {
+                return (TokenKind::Malformed(ParseErrorKind::UnterminatedString), "");
+            }
+            Err(err) => {
+                error.get_or_insert(err);
+                let mut chars = source[1..].chars();
+                chars.next();
+                chars.as_str()
+            }
+        };
+    }
+
+    let kind = error.map_or(TokenKind::String(string), TokenKind::Malformed);
+    (kind, source)
 }
 
 pub fn lex(mut source: &str) -> Vec<Token> {
     let mut tokens = Vec::new();
+    let mut position = Position::new(source);
 
     loop {
         source = source.trim_start();
-        source = if let Some(s) = source.strip_prefix('(') {
-            tokens.push(Token::LParen);
-            s
+        position.advance_to(source);
+        let mut push = |kind, rest| {
+            tokens.push(Token {
+                kind,
+                span: position.span_to(rest),
+            });
+            rest
+        };
+        source = if let Some(s) = source.strip_prefix(';') {
+            s.find('\n').map_or("", |line_end| &s[line_end..])
+        } else if let Some(s) = source.strip_prefix("#|") {
+            skip_block_comment(s).unwrap_or_else(|| {
+                push(
+                    TokenKind::Malformed(ParseErrorKind::UnterminatedBlockComment),
+                    "",
+                )
+            })
+        } else if let Some(s) = source.strip_prefix("#;") {
+            push(TokenKind::DatumComment, s)
+        } else if let Some(s) = source.strip_prefix('(') {
+            push(TokenKind::LParen, s)
         } else if let Some(s) = source.strip_prefix(')') {
-            tokens.push(Token::RParen);
-            s
+            push(TokenKind::RParen, s)
         } else if let Some(s) = source.strip_prefix('\'') {
-            tokens.push(Token::Quote);
-            s
+            push(TokenKind::Quote, s)
+        } else if let Some(s) = source.strip_prefix("#\\") {
+            let (kind, s) = lex_char(s);
+            push(kind, s)
+        } else if let Some(s) = source.strip_prefix('"') {
+            let (kind, s) = lex_string(s);
+            push(kind, s)
         } else {
-            let ident_end = source
-                .find(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '\''))
-                .unwrap_or(source.len());
+            let ident_end = source.find(is_delimiter).unwrap_or(source.len());
             if ident_end == 0 {
                 break;
             }
             let (ident, s) = source.split_at(ident_end);
-            tokens.push(Token::Ident(ident.to_owned()));
-            s
+            push(TokenKind::Ident(ident.to_owned()), s)
         }
     }
 
@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::nursery, clippy::pedantic)]
+#![allow(clippy::missing_errors_doc, clippy::must_use_candidate)]
+
+pub mod functions;
+mod interpreter;
+pub mod lexer;
+pub mod native;
+pub mod parser;
+pub mod span;
+pub mod types;
+mod wrapped;
+pub use interpreter::Interpreter;
@@ -1,73 +1,138 @@
 #![forbid(unsafe_code)]
 #![warn(clippy::nursery, clippy::pedantic)]
 
-mod functions;
-mod lexer;
-mod parser;
-mod types;
-mod wrapped;
+mod cli;
+mod repl;
 use anyhow::{Context, Result};
-use lexer::lex;
-use parser::parse_expressions;
-use std::{fs, rc::Rc};
-use types::{BuiltinFunction, Cons, Object};
+use cli::{Options, Source, USAGE};
+use rust_lisp::{
+    types::{Cons, Object},
+    Interpreter,
+};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+    rc::Rc,
+};
 
-macro_rules! make_env {
-    ($($name:literal = $value:expr),*) => {
-        make_list![
-        $(
-            Rc::new(Object::Cons(Cons(Some((
-                Rc::new(Object::Symbol($name.parse().unwrap())),
-                $value
-            )))))
-        ),*
-        ]
+fn make_args_list(script_args: Vec<OsString>) -> Object {
+    Object::Cons(
+        script_args
+            .into_iter()
+            .map(|arg| Rc::new(Object::String(arg.to_string_lossy().into_owned())))
+            .collect::<Cons>(),
+    )
+}
+
+#[derive(Clone, Copy)]
+enum Echo {
+    ExpressionsAndResults,
+    Results,
+    Nothing,
+}
+
+fn read_source(source: &Source) -> Result<String> {
+    match source {
+        Source::File(path) => fs::read_to_string(path)
+            .with_context(|| format!("failed to read source file {}", path.display())),
+        Source::Stdin => {
+            let mut source_code = String::new();
+            io::stdin()
+                .read_to_string(&mut source_code)
+                .context("failed to read standard input")?;
+            Ok(source_code)
+        }
+        Source::Expression(expr) => Ok(expr.clone()),
     }
 }
 
-fn main() -> Result<()> {
-    let builtin_function = |func| Rc::new(Object::BuiltinFunction(BuiltinFunction(func)));
+/// Evaluates every expression in `source_code`, returning whether all of
+/// them succeeded.
+fn run_source(
+    source_name: &str,
+    source_code: &str,
+    interpreter: &mut Interpreter,
+    echo: Echo,
+) -> Result<bool> {
+    let mut succeeded = true;
+
+    for e in interpreter.parse(source_name, source_code)? {
+        if matches!(echo, Echo::ExpressionsAndResults) {
+            println!("{e:#}");
+        }
+        match interpreter.eval(&e) {
+            Ok(result) => match echo {
+                Echo::ExpressionsAndResults => println!("=> {result:#}"),
+                Echo::Results => println!("{result:#}"),
+                Echo::Nothing => {}
+            },
+            Err(err) => {
+                eprintln!("{err}");
+                succeeded = false;
+            }
+        }
+    }
 
-    let mut env = make_env![
-        "car" = builtin_function(wrapped::car),
-        "cdr" = builtin_function(wrapped::cdr),
-        "cons" = builtin_function(wrapped::cons),
-        "lambda" = builtin_function(wrapped::lambda),
-        "+" = builtin_function(wrapped::add),
-        "-" = builtin_function(wrapped::sub),
-        "*" = builtin_function(wrapped::mul),
-        "quote" = builtin_function(wrapped::quote),
-        "int->bool" = builtin_function(wrapped::int_to_bool),
-        "bool->int" = builtin_function(wrapped::bool_to_int),
-        "and" = builtin_function(wrapped::and),
-        "or" = builtin_function(wrapped::or),
-        "not" = builtin_function(wrapped::not),
-        "define" = builtin_function(wrapped::define),
-        "nil?" = builtin_function(wrapped::is_nil),
-        "int?" = builtin_function(wrapped::is_int),
-        "bool?" = builtin_function(wrapped::is_bool),
-        "if" = builtin_function(wrapped::r#if),
-        "true" = Rc::new(Object::Bool(true)),
-        "false" = Rc::new(Object::Bool(false))
-    ];
+    Ok(succeeded)
+}
 
-    let source_code = fs::read_to_string("program.lisp").context("failed to read source file")?;
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args_os().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
 
-    let lexed = lex(&source_code);
-    let (exprs, _) = parse_expressions(&lexed).context("failed to parse source code")?;
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .define_global("*args*", make_args_list(options.script_args))
+        .unwrap();
 
-    for e in exprs {
-        println!("{e}");
-        match Rc::new(e).eval(&env) {
-            Ok((result, new_env)) => {
-                env = new_env;
-                println!("=> {result}");
+    if options.sources.is_empty() {
+        return match repl::run(&mut interpreter) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
             }
+        };
+    }
+
+    let mut succeeded = true;
+    for source in &options.sources {
+        let echo = match source {
+            _ if options.quiet => Echo::Nothing,
+            Source::Expression(_) => Echo::Results,
+            Source::File(_) | Source::Stdin => Echo::ExpressionsAndResults,
+        };
+        let source_name = match source {
+            Source::File(path) => path.display().to_string(),
+            Source::Stdin => String::from("<stdin>"),
+            Source::Expression(_) => String::from("<eval>"),
+        };
+        let result = read_source(source)
+            .and_then(|code| run_source(&source_name, &code, &mut interpreter, echo));
+        match result {
+            Ok(source_succeeded) => succeeded &= source_succeeded,
             Err(err) => {
-                println!("{err}");
+                eprintln!("{err:#}");
+                return ExitCode::FAILURE;
             }
         }
     }
 
-    Ok(())
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
@@ -0,0 +1,219 @@
+use crate::{
+    functions::{self, ensure_n_args, make_type_error},
+    types::{Cons, Environment, Evaluation, Object, Symbol},
+};
+use anyhow::{bail, ensure, Error, Result};
+use num_bigint::BigInt;
+use std::rc::Rc;
+
+/// Conversion from an evaluated argument to a Rust value. Returning `None`
+/// makes the caller report a type error.
+pub trait FromLisp: Sized {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self>;
+}
+
+pub trait IntoLisp {
+    fn into_lisp(self) -> Rc<Object>;
+}
+
+impl FromLisp for Rc<Object> {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        Some(obj.clone())
+    }
+}
+
+impl FromLisp for i64 {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Integer(inner) => Some(*inner),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for BigInt {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Integer(inner) => Some((*inner).into()),
+            Object::BigInteger(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Integers are converted to the nearest float.
+impl FromLisp for f64 {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        functions::exact_to_inexact(obj).ok()
+    }
+}
+
+/// Integers that do not fit are treated as having the wrong type.
+impl FromLisp for i32 {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Integer(inner) => Self::try_from(*inner).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for bool {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Bool(inner) => Some(*inner),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for String {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::String(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for char {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Char(inner) => Some(*inner),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for Symbol {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Symbol(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromLisp for Cons {
+    fn from_lisp(obj: &Rc<Object>) -> Option<Self> {
+        match &**obj {
+            Object::Cons(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl IntoLisp for Rc<Object> {
+    fn into_lisp(self) -> Rc<Object> {
+        self
+    }
+}
+
+impl<T: Into<Object>> IntoLisp for T {
+    fn into_lisp(self) -> Rc<Object> {
+        Rc::new(self.into())
+    }
+}
+
+/// The remaining arguments of a variadic native function, which must be its
+/// last parameter.
+pub struct Rest<T>(pub Vec<T>);
+
+/// A Rust function whose parameters can all be converted with [`FromLisp`]
+/// and whose result can be converted with [`IntoLisp`].
+pub trait NativeFunction<Args> {
+    /// The number of arguments, or the minimum number if the function takes
+    /// [`Rest`].
+    const ARITY: usize;
+    const VARIADIC: bool;
+
+    fn call(&self, func_name: &str, args: &[Rc<Object>]) -> Result<Rc<Object>>;
+}
+
+fn type_error(func_name: &str, args: &[Rc<Object>]) -> Error {
+    make_type_error(
+        func_name,
+        &args.iter().map(|arg| &**arg).collect::<Vec<_>>(),
+    )
+}
+
+macro_rules! impl_native_function {
+    ($($arg_type:ident $arg:ident),*) => {
+        impl<F, R, $($arg_type),*> NativeFunction<($($arg_type,)*)> for F
+        where
+            F: Fn($($arg_type),*) -> Result<R>,
+            R: IntoLisp,
+            $($arg_type: FromLisp),*
+        {
+            const ARITY: usize = <[&str]>::len(&[$(stringify!($arg)),*]);
+            const VARIADIC: bool = false;
+
+            fn call(&self, func_name: &str, args: &[Rc<Object>]) -> Result<Rc<Object>> {
+                let [$($arg),*] = args else {
+                    bail!("{func_name} expected {} arguments but got {}", Self::ARITY, args.len());
+                };
+                Ok(self($($arg_type::from_lisp($arg).ok_or_else(|| type_error(func_name, args))?),*)?.into_lisp())
+            }
+        }
+    };
+}
+
+macro_rules! impl_variadic_native_function {
+    ($($arg_type:ident $arg:ident),*) => {
+        impl<F, R, T, $($arg_type),*> NativeFunction<($($arg_type,)* Rest<T>,)> for F
+        where
+            F: Fn($($arg_type,)* Rest<T>) -> Result<R>,
+            R: IntoLisp,
+            T: FromLisp,
+            $($arg_type: FromLisp),*
+        {
+            const ARITY: usize = <[&str]>::len(&[$(stringify!($arg)),*]);
+            const VARIADIC: bool = true;
+
+            #[allow(irrefutable_let_patterns, clippy::redundant_at_rest_pattern)]
+            fn call(&self, func_name: &str, args: &[Rc<Object>]) -> Result<Rc<Object>> {
+                let [$($arg,)* rest @ ..] = args else {
+                    bail!("{func_name} expected at least {} arguments but got {}", Self::ARITY, args.len());
+                };
+                let rest = rest
+                    .iter()
+                    .map(|arg| T::from_lisp(arg).ok_or_else(|| type_error(func_name, args)))
+                    .collect::<Result<_>>()?;
+                Ok(self($($arg_type::from_lisp($arg).ok_or_else(|| type_error(func_name, args))?,)* Rest(rest))?.into_lisp())
+            }
+        }
+    };
+}
+
+impl_native_function!();
+impl_native_function!(A a);
+impl_native_function!(A a, B b);
+impl_native_function!(A a, B b, C c);
+impl_native_function!(A a, B b, C c, D d);
+impl_native_function!(A a, B b, C c, D d, E e);
+impl_variadic_native_function!();
+impl_variadic_native_function!(A a);
+impl_variadic_native_function!(A a, B b);
+
+/// Calls `func` the way a builtin would be called: checks the number of
+/// arguments, evaluates each of them in `env` and converts them.
+pub fn apply<Args: 'static, F: NativeFunction<Args> + 'static>(
+    func_name: &str,
+    func: Rc<F>,
+    args: &Cons,
+    env: &Environment,
+) -> Result<Evaluation> {
+    if F::VARIADIC {
+        ensure!(
+            args.is_proper_list(),
+            "call to {func_name} must be a proper list"
+        );
+    } else {
+        ensure_n_args(func_name, F::ARITY, args)?;
+    }
+    let func_name = Rc::<str>::from(func_name);
+    let env = env.clone();
+    Evaluation::then_all(args, &env.clone(), move |args| {
+        Ok(Evaluation::Done(func.call(&func_name, &args)?, env))
+    })
+}
@@ -1,104 +1,410 @@
 use crate::{
-    lexer::Token,
+    functions::parse_number,
+    lexer::{Token, TokenKind},
+    span::{SourceLocation, Span},
     types::{Cons, Object, Quote, Symbol},
 };
-use std::rc::Rc;
+use std::{fmt, rc::Rc, sync::Arc};
 
-fn parse_integer(tokens: &[Token]) -> Option<(i32, &[Token])> {
-    if let [Token::Ident(num_str), tokens @ ..] = tokens {
-        let num = num_str.parse().ok()?;
-        Some((num, tokens))
-    } else {
-        None
+/// Every parsed object along with the part of the source code it came from.
+pub type Spans = Vec<(Rc<Object>, Span)>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseErrorKind {
+    UnclosedParen,
+    StrayRParen,
+    MisplacedDot,
+    DanglingQuote,
+    DanglingDatumComment,
+    InvalidIdentifier(String),
+    UnterminatedString,
+    UnterminatedBlockComment,
+    InvalidEscape(String),
+    InvalidCharacter(String),
+    DivisionByZero(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedParen => f.write_str("unclosed parenthesis"),
+            Self::StrayRParen => f.write_str("unexpected `)`"),
+            Self::MisplacedDot => f.write_str("misplaced `.`"),
+            Self::DanglingQuote => f.write_str("`'` is not followed by an expression"),
+            Self::DanglingDatumComment => f.write_str("`#;` is not followed by an expression"),
+            Self::InvalidIdentifier(ident) => write!(f, "invalid identifier `{ident}`"),
+            Self::UnterminatedString => f.write_str("unterminated string"),
+            Self::UnterminatedBlockComment => f.write_str("unterminated block comment"),
+            Self::InvalidEscape(escape) => write!(f, "invalid escape sequence `{escape}`"),
+            Self::InvalidCharacter(name) => write!(f, "invalid character `#\\{name}`"),
+            Self::DivisionByZero(literal) => write!(f, "division by zero in `{literal}`"),
+        }
     }
 }
 
-fn parse_symbol(tokens: &[Token]) -> Option<(Symbol, &[Token])> {
-    if let [Token::Ident(symbol_str), tokens @ ..] = tokens {
-        let symbol = symbol_str.parse().ok()?;
-        Some((symbol, tokens))
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+/// All errors found while parsing a source file, ready to be shown to the
+/// user along with the lines they occurred on.
+#[derive(Debug)]
+pub struct ParseErrors {
+    file: Arc<str>,
+    errors: Vec<(ParseError, String)>,
+}
+
+impl ParseErrors {
+    pub fn new(file: &str, source: &str, errors: Vec<ParseError>) -> Self {
+        let errors = errors
+            .into_iter()
+            .map(|error| {
+                let line = source.lines().nth(error.span.line - 1).unwrap_or_default();
+                (error, line.to_owned())
+            })
+            .collect();
+        Self {
+            file: file.into(),
+            errors,
+        }
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ParseError> {
+        self.errors.iter().map(|(error, _)| error)
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (error, line)) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            let location = SourceLocation {
+                file: self.file.clone(),
+                span: error.span,
+            };
+            let line_number = error.span.line.to_string();
+            let gutter = " ".repeat(line_number.len());
+            let indent = " ".repeat(error.span.column - 1);
+            let width = (error.span.end - error.span.start)
+                .min(line.chars().count() + 1 - error.span.column)
+                .max(1);
+            writeln!(f, "{location}: {}", error.kind)?;
+            writeln!(f, "{line_number} | {line}")?;
+            write!(f, "{gutter} | {indent}{}", "^".repeat(width))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+type ParseResult<'a, T> = Result<(T, &'a [Token]), ParseError>;
+
+fn parse_atom(ident: &str, span: Span) -> Result<Object, ParseError> {
+    if ident == "." {
+        Err(ParseError {
+            kind: ParseErrorKind::MisplacedDot,
+            span,
+        })
+    } else if let Some(num) = parse_number(ident) {
+        num.map_err(|_| ParseError {
+            kind: ParseErrorKind::DivisionByZero(ident.to_owned()),
+            span,
+        })
+    } else if let Ok(symbol) = ident.parse::<Symbol>() {
+        Ok(Object::Symbol(symbol))
     } else {
-        None
+        Err(ParseError {
+            kind: ParseErrorKind::InvalidIdentifier(ident.to_owned()),
+            span,
+        })
     }
 }
 
-fn parse_lparen(tokens: &[Token]) -> Option<&[Token]> {
-    tokens.strip_prefix(&[Token::LParen])
+fn is_dot(token: &Token) -> bool {
+    matches!(&token.kind, TokenKind::Ident(s) if s == ".")
 }
 
-fn parse_rparen(tokens: &[Token]) -> Option<&[Token]> {
-    tokens.strip_prefix(&[Token::RParen])
+/// The span covering all tokens that were consumed to go from `tokens` to
+/// `remaining_tokens`.
+fn consumed_span(tokens: &[Token], remaining_tokens: &[Token]) -> Span {
+    let first = tokens[0].span;
+    let last = tokens[tokens.len() - remaining_tokens.len() - 1].span;
+    Span {
+        end: last.end,
+        ..first
+    }
 }
 
-fn parse_quote(tokens: &[Token]) -> Option<&[Token]> {
-    tokens.strip_prefix(&[Token::Quote])
+/// Where a list that is being parsed is at.
+enum ListState<'a> {
+    Elements,
+    /// After a `.`, waiting for the last cdr.
+    Tail(&'a Token),
+    /// After the last cdr, waiting for the `)`.
+    Closing(&'a Token, Rc<Object>),
 }
 
-fn parse_dot(tokens: &[Token]) -> Option<&[Token]> {
-    match tokens {
-        [Token::Ident(s), tokens @ ..] if s == "." => Some(tokens),
-        _ => None,
+/// An expression that is waiting for the one inside it to be parsed.
+enum Pending<'a> {
+    List {
+        lparen: &'a Token,
+        start: &'a [Token],
+        elements: Vec<Rc<Object>>,
+        state: ListState<'a>,
+    },
+    Quote {
+        quote: &'a Token,
+        start: &'a [Token],
+    },
+    DatumComment(&'a Token),
+}
+
+impl<'a> Pending<'a> {
+    /// Gives this expression the one inside it, which is `None` if there was
+    /// none before `tokens`. Returns the finished expression, or `None` if it
+    /// was put back on `pending` to wait for more.
+    fn accept(
+        self,
+        parsed: Option<Rc<Object>>,
+        tokens: &mut &'a [Token],
+        pending: &mut Vec<Self>,
+        spans: &mut Spans,
+    ) -> Result<Option<Rc<Object>>, ParseError> {
+        let unclosed = |lparen: &Token| ParseError {
+            kind: ParseErrorKind::UnclosedParen,
+            span: lparen.span,
+        };
+        let misplaced_dot = |dot: &Token| ParseError {
+            kind: ParseErrorKind::MisplacedDot,
+            span: dot.span,
+        };
+
+        let (expr, start) = match self {
+            Self::DatumComment(comment) => {
+                if parsed.is_none() {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::DanglingDatumComment,
+                        span: comment.span,
+                    });
+                }
+                return Ok(None);
+            }
+            Self::Quote { quote, start } => {
+                let Some(quoted) = parsed else {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::DanglingQuote,
+                        span: quote.span,
+                    });
+                };
+                (Rc::new(Object::Quote(Quote(quoted))), start)
+            }
+            Self::List {
+                lparen,
+                start,
+                mut elements,
+                state,
+            } => {
+                let last = match (state, parsed) {
+                    (ListState::Elements, Some(element)) => {
+                        elements.push(element);
+                        pending.push(Self::List {
+                            lparen,
+                            start,
+                            elements,
+                            state: ListState::Elements,
+                        });
+                        return Ok(None);
+                    }
+                    (ListState::Tail(dot), Some(last)) => {
+                        pending.push(Self::List {
+                            lparen,
+                            start,
+                            elements,
+                            state: ListState::Closing(dot, last),
+                        });
+                        return Ok(None);
+                    }
+                    (ListState::Tail(dot), None) => {
+                        return Err(if tokens.is_empty() {
+                            unclosed(lparen)
+                        } else {
+                            misplaced_dot(dot)
+                        });
+                    }
+                    (ListState::Closing(dot, _), Some(_)) => return Err(misplaced_dot(dot)),
+                    (ListState::Elements, None) => Rc::new(Object::Cons(Cons(None))),
+                    (ListState::Closing(_, last), None) => last,
+                };
+                let [_rparen, remaining_tokens @ ..] = *tokens else {
+                    return Err(unclosed(lparen));
+                };
+                *tokens = remaining_tokens;
+                (make_list(elements, last), start)
+            }
+        };
+        spans.push((expr.clone(), consumed_span(start, tokens)));
+        Ok(Some(expr))
     }
 }
 
-fn parse_quoted_expression(tokens: &[Token]) -> Option<(Quote, &[Token])> {
-    let remaining_tokens = parse_quote(tokens)?;
-    let (expr, unconsumed_tokens) = parse_expression(remaining_tokens)?;
-    Some((Quote(Rc::new(expr)), unconsumed_tokens))
-}
-
-fn parse_cons(tokens: &[Token]) -> Option<(Cons, &[Token])> {
-    fn parse_cons_helper(tokens: &[Token]) -> Option<(Cons, &[Token])> {
-        if let Some(unconsumed_tokens) = parse_rparen(tokens) {
-            Some((Cons(None), unconsumed_tokens))
-        } else {
-            let (first_expr, remaining_tokens) = parse_expression(tokens)?;
-            if let Some(remaining_tokens) = parse_dot(remaining_tokens) {
-                let (last_expr, remaining_tokens) = parse_expression(remaining_tokens)?;
-                let unconsumed_tokens = parse_rparen(remaining_tokens)?;
-                Some((
-                    Cons(Some((Rc::new(first_expr), Rc::new(last_expr)))),
-                    unconsumed_tokens,
-                ))
-            } else {
-                let (rest, remaining_tokens) = parse_cons_helper(remaining_tokens)?;
-                Some((
-                    Cons(Some((Rc::new(first_expr), Rc::new(Object::Cons(rest))))),
-                    remaining_tokens,
-                ))
+/// Builds a list out of `elements` followed by `last`.
+fn make_list(elements: Vec<Rc<Object>>, last: Rc<Object>) -> Rc<Object> {
+    elements.into_iter().rev().fold(last, |rest, element| {
+        Rc::new(Object::Cons(Cons(Some((element, rest)))))
+    })
+}
+
+/// Parses the next expression, skipping any `#;` comments before it. Returns
+/// `None` without consuming anything if there are no more tokens or if the
+/// next one is `)`, since only the caller knows whether that is an error.
+/// Nested expressions are kept track of on the heap, so that deeply nested
+/// ones can not overflow the stack.
+fn parse_next<'a>(
+    mut tokens: &'a [Token],
+    spans: &mut Spans,
+) -> ParseResult<'a, Option<Rc<Object>>> {
+    let mut pending = Vec::<Pending<'a>>::new();
+    loop {
+        if let (
+            [dot, rest @ ..],
+            Some(Pending::List {
+                elements, state, ..
+            }),
+        ) = (tokens, pending.last_mut())
+        {
+            if is_dot(dot) && matches!(state, ListState::Elements) {
+                if elements.is_empty() {
+                    return Err(ParseError {
+                        kind: ParseErrorKind::MisplacedDot,
+                        span: dot.span,
+                    });
+                }
+                *state = ListState::Tail(dot);
+                tokens = rest;
+                continue;
+            }
+        }
+
+        // Start parsing the expression at `tokens`, unless it is an atom,
+        // which can be finished right away.
+        let mut parsed = None;
+        if let Some((first, rest)) = tokens.split_first() {
+            let atom = match &first.kind {
+                TokenKind::RParen => None,
+                TokenKind::DatumComment => {
+                    pending.push(Pending::DatumComment(first));
+                    tokens = rest;
+                    continue;
+                }
+                TokenKind::LParen => {
+                    pending.push(Pending::List {
+                        lparen: first,
+                        start: tokens,
+                        elements: Vec::new(),
+                        state: ListState::Elements,
+                    });
+                    tokens = rest;
+                    continue;
+                }
+                TokenKind::Quote => {
+                    pending.push(Pending::Quote {
+                        quote: first,
+                        start: tokens,
+                    });
+                    tokens = rest;
+                    continue;
+                }
+                TokenKind::Ident(ident) => Some(parse_atom(ident, first.span)?),
+                TokenKind::String(string) => Some(Object::String(string.clone())),
+                TokenKind::Char(c) => Some(Object::Char(*c)),
+                TokenKind::Malformed(kind) => {
+                    return Err(ParseError {
+                        kind: kind.clone(),
+                        span: first.span,
+                    });
+                }
+            };
+            if let Some(atom) = atom {
+                let atom = Rc::new(atom);
+                spans.push((atom.clone(), first.span));
+                parsed = Some(atom);
+                tokens = rest;
             }
         }
-    }
 
-    let remaining_tokens = parse_lparen(tokens)?;
-    parse_cons_helper(remaining_tokens)
+        // Hand what was parsed to the expressions waiting for it, finishing
+        // as many of them as possible.
+        loop {
+            let Some(waiting) = pending.pop() else {
+                return Ok((parsed, tokens));
+            };
+            parsed = waiting.accept(parsed, &mut tokens, &mut pending, spans)?;
+            if parsed.is_none() {
+                break;
+            }
+        }
+    }
 }
 
-pub fn parse_expression(tokens: &[Token]) -> Option<(Object, &[Token])> {
-    if let Some((expr, tokens)) = parse_cons(tokens) {
-        Some((Object::Cons(expr), tokens))
-    } else if let Some((expr, tokens)) = parse_quoted_expression(tokens) {
-        Some((Object::Quote(expr), tokens))
-    } else if let Some((expr, tokens)) = parse_integer(tokens) {
-        Some((Object::Integer(expr), tokens))
-    } else if let Some((expr, tokens)) = parse_symbol(tokens) {
-        Some((Object::Symbol(expr), tokens))
-    } else {
-        None
+/// Skips past the top-level expression that `tokens` starts with, so that
+/// parsing can continue after an error.
+fn skip_expression(tokens: &[Token]) -> &[Token] {
+    let mut depth = 0_usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::LParen => depth += 1,
+            TokenKind::RParen => depth = depth.saturating_sub(1),
+            TokenKind::Quote | TokenKind::DatumComment => continue,
+            TokenKind::Ident(_)
+            | TokenKind::String(_)
+            | TokenKind::Char(_)
+            | TokenKind::Malformed(_) => {}
+        }
+        if depth == 0 {
+            return &tokens[i + 1..];
+        }
     }
+    &[]
 }
 
-pub fn parse_expressions(mut tokens: &[Token]) -> Option<(Vec<Object>, &[Token])> {
+/// Parses every expression in `tokens`. If some of them are malformed, as
+/// many errors as possible are collected instead.
+pub fn parse_expressions(
+    mut tokens: &[Token],
+) -> Result<(Vec<Rc<Object>>, Spans), Vec<ParseError>> {
     let mut ret = Vec::new();
+    let mut spans = Vec::new();
+    let mut errors = Vec::new();
 
-    while let Some((expr, remaining_tokens)) = parse_expression(tokens) {
-        ret.push(expr);
-        tokens = remaining_tokens;
+    while !tokens.is_empty() {
+        match parse_next(tokens, &mut spans) {
+            Ok((Some(expr), remaining_tokens)) => {
+                ret.push(expr);
+                tokens = remaining_tokens;
+            }
+            Ok((None, [])) => break,
+            Ok((None, [rparen, remaining_tokens @ ..])) => {
+                errors.push(ParseError {
+                    kind: ParseErrorKind::StrayRParen,
+                    span: rparen.span,
+                });
+                tokens = remaining_tokens;
+            }
+            Err(error) => {
+                errors.push(error);
+                tokens = skip_expression(tokens);
+            }
+        }
     }
 
-    if tokens.is_empty() {
-        Some((ret, tokens))
+    if errors.is_empty() {
+        Ok((ret, spans))
     } else {
-        None
+        Err(errors)
     }
 }
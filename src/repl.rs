@@ -0,0 +1,110 @@
+use anyhow::Result;
+use rust_lisp::{
+    lexer::{lex, Token, TokenKind},
+    parser::ParseErrorKind,
+    types::{Cons, Object},
+    Interpreter,
+};
+use rustyline::{error::ReadlineError, DefaultEditor};
+use std::{env, path::PathBuf};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+const LAST_RESULT_NAME: &str = "_";
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".rust_lisp_history"))
+}
+
+/// Whether the source still has `(`s, a string or a block comment that have
+/// not been closed, meaning that more lines need to be read before it can be
+/// parsed.
+fn is_incomplete(tokens: &[Token]) -> bool {
+    let mut depth = 0_usize;
+    for token in tokens {
+        match token.kind {
+            TokenKind::LParen => depth += 1,
+            TokenKind::RParen => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth > 0
+        || tokens.last().is_some_and(|token| {
+            matches!(
+                token.kind,
+                TokenKind::Quote
+                    | TokenKind::DatumComment
+                    | TokenKind::Malformed(
+                        ParseErrorKind::UnterminatedString
+                            | ParseErrorKind::UnterminatedBlockComment
+                    )
+            )
+        })
+}
+
+fn eval_source(source: &str, interpreter: &mut Interpreter) {
+    let exprs = match interpreter.parse("<repl>", source) {
+        Ok(exprs) => exprs,
+        Err(err) => {
+            eprintln!("{err}");
+            return;
+        }
+    };
+
+    for e in exprs {
+        match interpreter.eval(&e) {
+            Ok(result) => {
+                println!("{result:#}");
+                interpreter.define_global(LAST_RESULT_NAME, result).unwrap();
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                break;
+            }
+        }
+    }
+}
+
+pub fn run(interpreter: &mut Interpreter) -> Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history yet the first time the REPL is started.
+        let _ = editor.load_history(path);
+    }
+
+    interpreter.define_global(LAST_RESULT_NAME, Object::Cons(Cons(None)))?;
+    let mut source = String::new();
+
+    loop {
+        let prompt = if source.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                source.push_str(&line);
+                source.push('\n');
+                if is_incomplete(&lex(&source)) {
+                    continue;
+                }
+                if !source.trim().is_empty() {
+                    editor.add_history_entry(source.trim_end())?;
+                    eval_source(&source, interpreter);
+                }
+                source.clear();
+            }
+            Err(ReadlineError::Interrupted) => source.clear(),
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::types::Object;
+use anyhow::Error;
+use std::{
+    collections::HashMap,
+    fmt,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+/// A range of bytes in the source code, along with the line and column (both
+/// starting at 1) where it starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SourceLocation {
+    pub file: Arc<str>,
+    pub span: Span,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.span.line, self.span.column)
+    }
+}
+
+/// Remembers where parsed objects came from.
+///
+/// Nothing is stored in the objects themselves. Entries are keyed by address
+/// and hold a weak reference so that an address that has been reused by
+/// another object is not mistaken for the original.
+#[derive(Default)]
+pub struct SpanTable(HashMap<usize, (Weak<Object>, SourceLocation)>);
+
+impl SpanTable {
+    pub fn insert(&mut self, obj: &Rc<Object>, location: SourceLocation) {
+        self.0
+            .insert(Rc::as_ptr(obj) as usize, (Rc::downgrade(obj), location));
+    }
+
+    pub fn get(&self, address: usize) -> Option<&SourceLocation> {
+        self.0
+            .get(&address)
+            .filter(|(obj, _)| obj.strong_count() > 0)
+            .map(|(_, location)| location)
+    }
+
+    /// Forgets objects that no longer exist.
+    pub fn prune(&mut self) {
+        self.0.retain(|_, (obj, _)| obj.strong_count() > 0);
+    }
+}
+
+/// An error raised while evaluating, together with the expressions that were
+/// being evaluated when it happened, innermost first.
+pub struct EvalError {
+    error: Error,
+    expressions: Vec<usize>,
+    location: Option<SourceLocation>,
+}
+
+impl EvalError {
+    /// Records that `err` happened while evaluating `expr`.
+    pub fn add_expression(err: Error, expr: &Rc<Object>) -> Error {
+        let address = Rc::as_ptr(expr) as usize;
+        match err.downcast::<Self>() {
+            Ok(mut eval_error) => {
+                eval_error.expressions.push(address);
+                eval_error.into()
+            }
+            Err(error) => Self {
+                error,
+                expressions: vec![address],
+                location: None,
+            }
+            .into(),
+        }
+    }
+
+    /// Attaches the location of the innermost expression that `spans` knows
+    /// about to `err`.
+    pub fn locate(err: Error, spans: &SpanTable) -> Error {
+        match err.downcast::<Self>() {
+            Ok(mut eval_error) => {
+                if eval_error.location.is_none() {
+                    eval_error.location = eval_error
+                        .expressions
+                        .iter()
+                        .find_map(|&address| spans.get(address))
+                        .cloned();
+                }
+                eval_error.into()
+            }
+            Err(err) => err,
+        }
+    }
+
+    pub const fn location(&self) -> Option<&SourceLocation> {
+        self.location.as_ref()
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(f, "{location}: {}", self.error),
+            None => self.error.fmt(f),
+        }
+    }
+}
+
+impl fmt::Debug for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for EvalError {}
@@ -8,5 +8,11 @@ mod function;
 pub use function::*;
 mod cons;
 pub use cons::*;
+mod rational;
+pub use rational::*;
+mod environment;
+pub use environment::*;
+mod evaluation;
+pub use evaluation::*;
 mod object;
 pub use object::*;
@@ -1,19 +1,87 @@
-use crate::types::{Cons, Object};
+use crate::{
+    native::{self, NativeFunction},
+    types::{Cons, Environment, Evaluation, Object},
+};
 use anyhow::Result;
-use std::{fmt, rc::Rc};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
-type FnType = fn(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)>;
+type FnType = dyn Fn(&str, &Cons, &Environment) -> Result<Evaluation>;
 
-pub struct BuiltinFunction(pub FnType);
+#[derive(Clone)]
+pub struct BuiltinFunction {
+    name: Rc<str>,
+    func: Rc<FnType>,
+}
 
 impl fmt::Display for BuiltinFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("Builtin function")
+        write!(f, "Builtin function {}", self.name)
+    }
+}
+
+/// Builtins are equal if they wrap the same function.
+impl PartialEq for BuiltinFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.func, &other.func)
+    }
+}
+
+impl Hash for BuiltinFunction {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.func).cast::<()>().hash(state);
     }
 }
 
 impl BuiltinFunction {
-    pub fn apply(&self, args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-        (self.0)(args, env)
+    /// Wraps `func`, which receives its arguments unevaluated along with the
+    /// environment of the caller. Closures can capture host state, so this can
+    /// be used for things that a plain `fn` can not express. Special forms
+    /// can return an [`Evaluation`] to have an expression in tail position
+    /// evaluated by the caller.
+    pub fn new<R: Into<Evaluation>>(
+        name: &str,
+        func: impl Fn(&Cons, &Environment) -> Result<R> + 'static,
+    ) -> Self {
+        Self::named(name, move |_, args, env| func(args, env))
+    }
+
+    /// Like [`BuiltinFunction::new`], but `func` is also passed the name that
+    /// the builtin was created with, for use in error messages.
+    pub fn named<R: Into<Evaluation>>(
+        name: &str,
+        func: impl Fn(&str, &Cons, &Environment) -> Result<R> + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            func: Rc::new(move |name, args, env| func(name, args, env).map(Into::into)),
+        }
+    }
+
+    /// Wraps a function with ordinary Rust parameters. The arguments are
+    /// evaluated, counted and converted before `func` is called.
+    pub fn from_native<Args: 'static>(
+        name: &str,
+        func: impl NativeFunction<Args> + 'static,
+    ) -> Self {
+        let func = Rc::new(func);
+        Self::named(name, move |name, args, env| {
+            native::apply(name, func.clone(), args, env)
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn apply(&self, args: &Cons, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+        self.apply_tail(args, env)?.finish()
+    }
+
+    pub fn apply_tail(&self, args: &Cons, env: &Environment) -> Result<Evaluation> {
+        (self.func)(self.name(), args, env)
     }
 }
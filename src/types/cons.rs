@@ -1,19 +1,17 @@
-use crate::types::Object;
+use crate::types::{Environment, Evaluation, Object, Quote};
 use anyhow::{bail, Result};
-use std::{fmt, rc::Rc};
+use std::{fmt, mem, rc::Rc};
 
 #[derive(Clone)]
 pub struct Cons(pub Option<(Rc<Object>, Rc<Object>)>);
 
 impl Cons {
     pub fn len(&self) -> usize {
-        match &self.0 {
-            None => 0,
-            Some((_, next)) => match &**next {
-                Object::Cons(rest) => rest.len() + 1,
-                _ => 1,
-            },
-        }
+        self.iter().count()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_none()
     }
 
     pub fn car(&self) -> Rc<Object> {
@@ -31,41 +29,194 @@ impl Cons {
     }
 
     pub fn is_proper_list(&self) -> bool {
-        match &self.0 {
-            None => true,
-            Some((_, next)) => match &**next {
-                Object::Cons(rest) => rest.is_proper_list(),
-                _ => false,
-            },
+        let mut list = self;
+        loop {
+            match &list.0 {
+                None => return true,
+                Some((_, next)) => match &**next {
+                    Object::Cons(rest) => list = rest,
+                    _ => return false,
+                },
+            }
         }
     }
 
-    pub fn eval(&self, env: &Self) -> Result<(Rc<Object>, Self)> {
+    /// Iterates over the elements of the list, stopping at the first cdr that
+    /// is not a cons.
+    pub fn iter(&self) -> Iter {
+        Iter(self.clone())
+    }
+
+    /// Evaluates the list as a call, leaving the expression in tail position
+    /// of the called function for the caller to evaluate.
+    pub fn eval_tail(&self, env: &Environment) -> Result<Evaluation> {
         let Object::Cons(args) = &*self.cdr() else {
             bail!("cdr of argument passed to eval_cons must be a cons");
         };
-        let (func, env) = self.car().eval(env)?;
-        func.apply(args, &env)
+        let (args, env) = (args.clone(), env.clone());
+        Ok(Evaluation::then(
+            self.car(),
+            &env.clone(),
+            move |func, _| func.apply_tail(&args, &env),
+        ))
+    }
+}
+
+/// Compares lists element by element, keeping track of nested lists and
+/// quotes on the heap so that neither long nor deeply nested lists can
+/// overflow the stack.
+impl PartialEq for Cons {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = Vec::new();
+        let (mut lhs, mut rhs) = (self, other);
+        loop {
+            match (&lhs.0, &rhs.0) {
+                (None, None) => {}
+                (Some((lhs_first, lhs_rest)), Some((rhs_first, rhs_rest))) => {
+                    pending.push((&**lhs_rest, &**rhs_rest));
+                    pending.push((&**lhs_first, &**rhs_first));
+                }
+                _ => return false,
+            }
+            // Everything up to the next pair of lists is compared right away.
+            loop {
+                match pending.pop() {
+                    None => return true,
+                    Some((Object::Cons(lhs_list), Object::Cons(rhs_list))) => {
+                        lhs = lhs_list;
+                        rhs = rhs_list;
+                        break;
+                    }
+                    Some((Object::Quote(Quote(lhs_quoted)), Object::Quote(Quote(rhs_quoted)))) => {
+                        pending.push((lhs_quoted, rhs_quoted));
+                    }
+                    Some((lhs_obj, rhs_obj)) if lhs_obj != rhs_obj => return false,
+                    Some(_) => {}
+                }
+            }
+        }
     }
 }
 
+/// Something that is left to print when displaying a list.
+enum Printing<'a> {
+    Object(&'a Object),
+    Str(&'static str),
+}
+
+/// Prints nested lists and quotes from a stack on the heap, so that deeply
+/// nested lists can be printed.
 impl fmt::Display for Cons {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        fn to_cons_string(obj: &Object) -> String {
-            match obj {
-                Object::Cons(Cons(None)) => String::new(),
-                Object::Cons(Cons(Some((first, second)))) => {
-                    format!(" {}{}", first, to_cons_string(second))
+        let mut pending = Vec::new();
+        let mut list = Some(self);
+        loop {
+            if let Some(list) = list.take() {
+                let Some((first, second)) = &list.0 else {
+                    write!(formatter, "()")?;
+                    continue;
+                };
+                write!(formatter, "(")?;
+                // The rest of the list is printed after `first`, so it is
+                // pushed in reverse.
+                let mut rest = Vec::new();
+                let mut next = second;
+                loop {
+                    match &**next {
+                        Object::Cons(Self(None)) => break,
+                        Object::Cons(Self(Some((element, after)))) => {
+                            rest.push(Printing::Str(" "));
+                            rest.push(Printing::Object(element));
+                            next = after;
+                        }
+                        obj => {
+                            rest.push(Printing::Str(" . "));
+                            rest.push(Printing::Object(obj));
+                            break;
+                        }
+                    }
+                }
+                pending.push(Printing::Str(")"));
+                pending.extend(rest.into_iter().rev());
+                pending.push(Printing::Object(first));
+            }
+            match pending.pop() {
+                None => return Ok(()),
+                Some(Printing::Str(s)) => write!(formatter, "{s}")?,
+                Some(Printing::Object(Object::Cons(cons))) => list = Some(cons),
+                Some(Printing::Object(Object::Quote(Quote(quoted)))) => {
+                    write!(formatter, "(quote ")?;
+                    pending.push(Printing::Str(")"));
+                    pending.push(Printing::Object(quoted));
                 }
-                _ => format!(" . {obj}"),
+                Some(Printing::Object(obj)) => obj.fmt(formatter)?,
             }
         }
+    }
+}
 
-        match &self.0 {
-            None => write!(formatter, "()"),
-            Some((first, second)) => {
-                write!(formatter, "({first}{})", to_cons_string(second))
+/// Frees long and deeply nested lists without recursing.
+impl Drop for Cons {
+    fn drop(&mut self) {
+        if let Some((first, rest)) = self.0.take() {
+            drop_nested(vec![first, rest]);
+        }
+    }
+}
+
+/// Drops `pending` by taking apart the lists and quotes in it that are not
+/// shared with anything else, keeping track of their contents on the heap
+/// instead of recursing.
+fn drop_nested(mut pending: Vec<Rc<Object>>) {
+    while let Some(obj) = pending.pop() {
+        match Rc::try_unwrap(obj) {
+            Ok(Object::Cons(mut cons)) => {
+                if let Some((first, rest)) = cons.0.take() {
+                    pending.push(first);
+                    pending.push(rest);
+                }
             }
+            Ok(Object::Quote(mut quote)) => {
+                pending.push(mem::replace(
+                    &mut quote.0,
+                    Rc::new(Object::Cons(Cons(None))),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl FromIterator<Rc<Object>> for Cons {
+    fn from_iter<T: IntoIterator<Item = Rc<Object>>>(iter: T) -> Self {
+        let mut elements = iter.into_iter().collect::<Vec<_>>();
+        let mut list = Self(None);
+        while let Some(element) = elements.pop() {
+            list = Self(Some((element, Rc::new(Object::Cons(list)))));
+        }
+        list
+    }
+}
+
+impl IntoIterator for &Cons {
+    type Item = Rc<Object>;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter(Cons);
+
+impl Iterator for Iter {
+    type Item = Rc<Object>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.0 .0.take()?;
+        if let Object::Cons(rest) = &*rest {
+            self.0 = rest.clone();
         }
+        Some(first)
     }
 }
@@ -0,0 +1,325 @@
+use crate::types::{Cons, Object, Quote, Symbol};
+use anyhow::{bail, Result};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    mem,
+    rc::{Rc, Weak},
+};
+
+/// The fewest frames that are created between two searches for reference
+/// cycles.
+const MIN_COLLECTION_INTERVAL: usize = 10_000;
+
+thread_local! {
+    /// Every frame that might be part of a reference cycle and that is still
+    /// alive, or that has died since the last search for reference cycles.
+    static FRAMES: RefCell<Vec<Weak<Frame>>> = const { RefCell::new(Vec::new()) };
+    /// How many more frames can be added to `FRAMES` before searching for
+    /// reference cycles again.
+    static FRAMES_UNTIL_COLLECTION: Cell<usize> = const { Cell::new(MIN_COLLECTION_INTERVAL) };
+}
+
+struct Frame {
+    bindings: RefCell<HashMap<Symbol, Rc<Object>>>,
+    parent: Option<Environment>,
+    /// Whether the frame has been added to `FRAMES`.
+    tracked: Cell<bool>,
+}
+
+/// Whether `obj` can refer to a frame, so that binding it in a frame can
+/// create a reference cycle.
+const fn can_refer_to_frame(obj: &Object) -> bool {
+    matches!(
+        obj,
+        Object::Function(_) | Object::Cons(Cons(Some(_))) | Object::Quote(_)
+    )
+}
+
+/// Frees long chains of frames without recursing, by taking apart the parents
+/// that are not shared with anything else.
+impl Drop for Frame {
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(Environment(frame)) = parent {
+            parent = Rc::try_unwrap(frame)
+                .ok()
+                .and_then(|mut frame| frame.parent.take());
+        }
+    }
+}
+
+/// A chain of frames of bindings. Frames can be changed in place, so every
+/// closure that shares a frame sees the same bindings.
+///
+/// A function that is bound in the frame that it was created in keeps that
+/// frame alive and the other way around, so frames are also freed by
+/// [`collect_cycles`] once nothing else refers to them.
+#[derive(Clone)]
+pub struct Environment(Rc<Frame>);
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Environment {
+    /// Creates an environment with a single empty frame.
+    pub fn new() -> Self {
+        Self(Rc::new(Frame {
+            bindings: RefCell::new(HashMap::new()),
+            parent: None,
+            tracked: Cell::new(false),
+        }))
+    }
+
+    /// Creates a new frame containing `bindings` on top of this environment.
+    #[must_use]
+    pub fn extend(&self, bindings: impl IntoIterator<Item = (Symbol, Rc<Object>)>) -> Self {
+        let bindings = bindings.into_iter().collect::<HashMap<_, _>>();
+        let may_be_cyclic = bindings.values().any(|value| can_refer_to_frame(value));
+        let env = Self(Rc::new(Frame {
+            bindings: RefCell::new(bindings),
+            parent: Some(self.clone()),
+            tracked: Cell::new(false),
+        }));
+        if may_be_cyclic {
+            env.track();
+        }
+        env
+    }
+
+    /// Keeps track of the innermost frame so that it can be freed if it ends
+    /// up in a reference cycle.
+    fn track(&self) {
+        if self.0.tracked.replace(true) {
+            return;
+        }
+        if FRAMES_UNTIL_COLLECTION.get() == 0 {
+            collect_cycles();
+        }
+        FRAMES_UNTIL_COLLECTION.set(FRAMES_UNTIL_COLLECTION.get().saturating_sub(1));
+        FRAMES.with_borrow_mut(|frames| frames.push(Rc::downgrade(&self.0)));
+    }
+
+    /// Binds `name` to `value` in the innermost frame, replacing any binding
+    /// of `name` that the frame already has.
+    pub fn define(&self, name: Symbol, value: Rc<Object>) {
+        if can_refer_to_frame(&value) {
+            self.track();
+        }
+        self.0.bindings.borrow_mut().insert(name, value);
+    }
+
+    /// Looks up the value of the nearest binding of `name`.
+    pub fn get(&self, name: &Symbol) -> Option<Rc<Object>> {
+        let mut env = self;
+        loop {
+            if let Some(value) = env.0.bindings.borrow().get(name) {
+                return Some(value.clone());
+            }
+            env = env.0.parent.as_ref()?;
+        }
+    }
+
+    /// Changes the value of the nearest binding of `name`.
+    pub fn set(&self, name: &Symbol, value: Rc<Object>) -> Result<()> {
+        let mut env = self;
+        while !env.0.bindings.borrow().contains_key(name) {
+            let Some(parent) = &env.0.parent else {
+                bail!("Unbound variable {name}");
+            };
+            env = parent;
+        }
+        env.define(name.clone(), value);
+        Ok(())
+    }
+}
+
+/// A frame or an object that can be part of a reference cycle.
+#[derive(Clone)]
+enum Node {
+    Frame(Rc<Frame>),
+    Object(Rc<Object>),
+}
+
+impl Node {
+    /// Objects that can not refer to a frame are left out.
+    fn from_object(obj: &Rc<Object>) -> Option<Self> {
+        can_refer_to_frame(obj).then(|| Self::Object(obj.clone()))
+    }
+
+    fn address(&self) -> *const () {
+        match self {
+            Self::Frame(frame) => Rc::as_ptr(frame).cast(),
+            Self::Object(obj) => Rc::as_ptr(obj).cast(),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Self::Frame(frame) => Rc::strong_count(frame),
+            Self::Object(obj) => Rc::strong_count(obj),
+        }
+    }
+
+    /// Calls `visit` with every node that this one refers to. Returns `false`
+    /// if that can not be known because the bindings of the frame are being
+    /// changed.
+    fn for_each_child(&self, mut visit: impl FnMut(Self)) -> bool {
+        match self {
+            Self::Frame(frame) => {
+                let Ok(bindings) = frame.bindings.try_borrow() else {
+                    return false;
+                };
+                if let Some(Environment(parent)) = &frame.parent {
+                    visit(Self::Frame(parent.clone()));
+                }
+                bindings
+                    .values()
+                    .filter_map(Self::from_object)
+                    .for_each(visit);
+            }
+            Self::Object(obj) => match &**obj {
+                Object::Function(func) => visit(Self::Frame(func.env().0.clone())),
+                Object::Cons(Cons(Some((first, rest)))) => {
+                    Self::from_object(first).into_iter().for_each(&mut visit);
+                    Self::from_object(rest).into_iter().for_each(visit);
+                }
+                Object::Quote(Quote(quoted)) => {
+                    Self::from_object(quoted).into_iter().for_each(visit);
+                }
+                _ => {}
+            },
+        }
+        true
+    }
+}
+
+/// Hashes addresses, which need neither the quality nor the resistance to
+/// attacks of the default hasher.
+#[derive(Default)]
+struct AddressHasher(u64);
+
+impl Hasher for AddressHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u8(byte);
+        }
+    }
+
+    fn write_u8(&mut self, byte: u8) {
+        self.write_usize(usize::from(byte));
+    }
+
+    fn write_usize(&mut self, address: usize) {
+        self.0 = (self.0 ^ address as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+}
+
+/// Everything that can be reached from the frames that are alive.
+#[derive(Default)]
+struct Graph {
+    nodes: Vec<Node>,
+    indices: HashMap<*const (), usize, BuildHasherDefault<AddressHasher>>,
+    /// How many references to each node come from other nodes, counting the
+    /// one in `nodes` too.
+    internal_counts: Vec<usize>,
+    /// Whether each node is known to be alive, because something outside of
+    /// the graph refers to it or to a node that refers to it.
+    alive: Vec<bool>,
+}
+
+impl Graph {
+    fn reference(&mut self, node: Node) {
+        if let Some(&index) = self.indices.get(&node.address()) {
+            self.internal_counts[index] += 1;
+        } else {
+            self.indices.insert(node.address(), self.nodes.len());
+            self.nodes.push(node);
+            self.internal_counts.push(2);
+            self.alive.push(false);
+        }
+    }
+
+    fn build(frames: Vec<Node>) -> Self {
+        let mut graph = Self::default();
+        for frame in frames {
+            graph.reference(frame);
+        }
+        // The frames were not referred to by another node.
+        graph.internal_counts.fill(1);
+        let mut index = 0;
+        while let Some(node) = graph.nodes.get(index).cloned() {
+            if !node.for_each_child(|child| graph.reference(child)) {
+                graph.alive[index] = true;
+            }
+            index += 1;
+        }
+        graph
+    }
+
+    /// Marks everything that is referred to from outside of the graph, and
+    /// everything that can be reached from that, as alive.
+    fn mark(&mut self) {
+        let mut pending = (0..self.nodes.len())
+            .filter(|&index| {
+                self.alive[index] || self.nodes[index].strong_count() > self.internal_counts[index]
+            })
+            .collect::<Vec<_>>();
+        for &index in &pending {
+            self.alive[index] = true;
+        }
+        while let Some(index) = pending.pop() {
+            self.nodes[index].clone().for_each_child(|child| {
+                if let Some(&child_index) = self.indices.get(&child.address()) {
+                    if !self.alive[child_index] {
+                        self.alive[child_index] = true;
+                        pending.push(child_index);
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Frees the frames that are only kept alive by reference cycles, such as a
+/// frame containing a function that was created in it.
+///
+/// This happens on its own every so often as frames that might be part of a
+/// cycle are created, so it only needs to be called to free the memory right
+/// away.
+pub fn collect_cycles() {
+    let frames = FRAMES.with_borrow_mut(|frames| {
+        frames.retain(|frame| frame.strong_count() > 0);
+        frames
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(Node::Frame)
+            .collect::<Vec<_>>()
+    });
+    let mut graph = Graph::build(frames);
+    graph.mark();
+    let unreachable = graph
+        .nodes
+        .iter()
+        .zip(&graph.alive)
+        .filter_map(|(node, &alive)| match node {
+            Node::Frame(frame) if !alive => Some(mem::take(&mut *frame.bindings.borrow_mut())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // The next search has to go through at least what is still alive, so
+    // waiting for at least as many frames to be tracked keeps the overall
+    // cost linear.
+    let alive = graph.alive.iter().filter(|&&alive| alive).count();
+    FRAMES_UNTIL_COLLECTION.set(alive.max(MIN_COLLECTION_INTERVAL));
+    drop(graph);
+    drop(unreachable);
+}
@@ -0,0 +1,227 @@
+use crate::{
+    span::EvalError,
+    types::{Environment, Object},
+};
+use anyhow::{anyhow, Result};
+use std::{cell::Cell, rc::Rc};
+
+/// The default for [`with_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 100_000;
+
+thread_local! {
+    static MAX_DEPTH: Cell<usize> = const { Cell::new(DEFAULT_MAX_DEPTH) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `f` with at most `max_depth` nested evaluations allowed at once.
+/// Going deeper than that fails with "stack depth exceeded" instead of
+/// using up all memory.
+pub fn with_max_depth<T>(max_depth: usize, f: impl FnOnce() -> T) -> T {
+    let previous = MAX_DEPTH.replace(max_depth);
+    let result = f();
+    MAX_DEPTH.set(previous);
+    result
+}
+
+/// What to do with the value of an expression once it has been evaluated.
+pub type Continuation = Box<dyn FnOnce(Rc<Object>, Environment) -> Result<Evaluation>>;
+
+/// The result of a single step of evaluation.
+///
+/// Special forms return [`Evaluation::Tail`] for expressions in tail position,
+/// so that evaluating those does not use any more of the Rust stack, and
+/// [`Evaluation::Then`] for other subexpressions, which are kept track of on
+/// the heap instead.
+pub enum Evaluation {
+    /// A value, along with the environment that the caller continues in.
+    Done(Rc<Object>, Environment),
+    /// `expr` still has to be evaluated in `env`. The caller continues in
+    /// `caller_env` afterwards, or in whatever environment evaluating `expr`
+    /// results in if it is `None`.
+    Tail {
+        expr: Rc<Object>,
+        env: Environment,
+        caller_env: Option<Environment>,
+    },
+    /// `expr` has to be evaluated in `env`, after which `then` is called with
+    /// its value and the environment that it resulted in.
+    Then {
+        expr: Rc<Object>,
+        env: Environment,
+        then: Continuation,
+    },
+}
+
+impl From<(Rc<Object>, Environment)> for Evaluation {
+    fn from((value, env): (Rc<Object>, Environment)) -> Self {
+        Self::Done(value, env)
+    }
+}
+
+/// An evaluation that is waiting for the value of a subexpression.
+struct Frame {
+    then: Continuation,
+    result_env: Option<Environment>,
+    expr: Option<Rc<Object>>,
+}
+
+impl Evaluation {
+    /// Evaluates `expr` in tail position, after which the caller continues in
+    /// `env`.
+    pub fn tail_call(expr: Rc<Object>, env: &Environment) -> Self {
+        Self::Tail {
+            expr,
+            env: env.clone(),
+            caller_env: Some(env.clone()),
+        }
+    }
+
+    /// Evaluates `expr` in `env` and continues with `then`.
+    pub fn then(
+        expr: Rc<Object>,
+        env: &Environment,
+        then: impl FnOnce(Rc<Object>, Environment) -> Result<Self> + 'static,
+    ) -> Self {
+        Self::Then {
+            expr,
+            env: env.clone(),
+            then: Box::new(then),
+        }
+    }
+
+    /// Evaluates each of `exprs` in `env`, in order, and continues with
+    /// `then` once all of their values are known.
+    pub fn then_all(
+        exprs: impl IntoIterator<Item = Rc<Object>>,
+        env: &Environment,
+        then: impl FnOnce(Vec<Rc<Object>>) -> Result<Self> + 'static,
+    ) -> Result<Self> {
+        fn eval_rest(
+            mut exprs: std::vec::IntoIter<Rc<Object>>,
+            mut values: Vec<Rc<Object>>,
+            env: Environment,
+            then: Box<dyn FnOnce(Vec<Rc<Object>>) -> Result<Evaluation>>,
+        ) -> Result<Evaluation> {
+            match exprs.next() {
+                None => then(values),
+                Some(expr) => Ok(Evaluation::then(expr, &env.clone(), move |value, _| {
+                    values.push(value);
+                    eval_rest(exprs, values, env, then)
+                })),
+            }
+        }
+
+        let exprs = exprs.into_iter().collect::<Vec<_>>();
+        let values = Vec::with_capacity(exprs.len());
+        eval_rest(exprs.into_iter(), values, env.clone(), Box::new(then))
+    }
+
+    /// Makes the caller continue in `env` no matter what the evaluation does
+    /// to its own environment.
+    #[must_use]
+    pub fn returning_to(self, env: &Environment) -> Self {
+        match self {
+            Self::Done(value, _) => Self::Done(value, env.clone()),
+            Self::Tail {
+                expr,
+                env: inner_env,
+                ..
+            } => Self::Tail {
+                expr,
+                env: inner_env,
+                caller_env: Some(env.clone()),
+            },
+            Self::Then {
+                expr,
+                env: inner_env,
+                then,
+            } => {
+                let env = env.clone();
+                Self::then(expr, &inner_env, move |value, inner_env| {
+                    Ok(then(value, inner_env)?.returning_to(&env))
+                })
+            }
+        }
+    }
+
+    /// Keeps evaluating until there is a value. Evaluations that are waiting
+    /// for a subexpression are kept on the heap, so how deep this can go is
+    /// limited by [`with_max_depth`] rather than by the size of the Rust
+    /// stack.
+    pub fn finish(self) -> Result<(Rc<Object>, Environment)> {
+        let mut frames = Vec::new();
+        let result = self.run(&mut frames);
+        DEPTH.set(DEPTH.get() - frames.len());
+        // Unwinding the frames tells the error which expressions were being
+        // evaluated, innermost first.
+        result.map_err(|err| {
+            frames
+                .into_iter()
+                .rev()
+                .filter_map(|frame| frame.expr)
+                .fold(err, |err, expr| EvalError::add_expression(err, &expr))
+        })
+    }
+
+    fn run(self, frames: &mut Vec<Frame>) -> Result<(Rc<Object>, Environment)> {
+        let mut evaluation = self;
+        let mut result_env = None;
+        let mut current_expr: Option<Rc<Object>> = None;
+        let annotate = |err, expr: &Option<Rc<Object>>| match expr {
+            Some(expr) => EvalError::add_expression(err, expr),
+            None => err,
+        };
+        loop {
+            evaluation = match evaluation {
+                Self::Done(value, env) => {
+                    let env = result_env.take().unwrap_or(env);
+                    let Some(frame) = frames.pop() else {
+                        return Ok((value, env));
+                    };
+                    DEPTH.set(DEPTH.get() - 1);
+                    result_env = frame.result_env;
+                    current_expr = frame.expr;
+                    (frame.then)(value, env).map_err(|err| annotate(err, &current_expr))?
+                }
+                Self::Tail {
+                    expr,
+                    env,
+                    caller_env,
+                } => {
+                    // The outermost caller decides which environment is
+                    // returned.
+                    result_env = result_env.or(caller_env);
+                    let next = expr.clone().eval_tail(&env)?;
+                    current_expr = Some(expr);
+                    next
+                }
+                Self::Then { expr, env, then } => {
+                    let next = expr
+                        .clone()
+                        .eval_tail(&env)
+                        .map_err(|err| annotate(err, &current_expr))?;
+                    if let Self::Done(value, env) = next {
+                        // Simple expressions such as symbols are done right
+                        // away, without needing a frame.
+                        then(value, env).map_err(|err| annotate(err, &current_expr))?
+                    } else {
+                        if DEPTH.get() >= MAX_DEPTH.get() {
+                            let err = anyhow!("stack depth exceeded");
+                            return Err(annotate(
+                                EvalError::add_expression(err, &expr),
+                                &current_expr,
+                            ));
+                        }
+                        DEPTH.set(DEPTH.get() + 1);
+                        frames.push(Frame {
+                            then,
+                            result_env: result_env.take(),
+                            expr: current_expr.replace(expr),
+                        });
+                        next
+                    }
+                }
+            };
+        }
+    }
+}
@@ -1,30 +1,77 @@
 use crate::{
-    functions::{eval_list_elements, join_two_lists_cons},
-    types::{Cons, Object},
+    functions::{ensure_n_args, eval_body},
+    types::{Cons, Environment, Evaluation, Object, Symbol},
 };
 use anyhow::Result;
-use std::{fmt, rc::Rc};
+use itertools::Itertools;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ptr,
+    rc::Rc,
+};
 
 pub struct Function {
-    parameters: Cons,
-    body: Rc<Object>,
+    parameters: Rc<[Symbol]>,
+    body: Cons,
+    env: Environment,
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Function {} => {}", self.parameters, self.body)
+        write!(f, "Function ({}) =>", self.parameters.iter().join(" "))?;
+        for expr in &self.body {
+            write!(f, " ")?;
+            expr.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Functions are only equal to themselves.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+impl Hash for Function {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ptr::hash(self, state);
     }
 }
 
 impl Function {
-    pub const fn new(parameters: Cons, body: Rc<Object>) -> Self {
-        Self { parameters, body }
+    /// Creates a function whose body consists of the expressions in `body`.
+    pub fn new(parameters: Vec<Symbol>, body: Cons, env: Environment) -> Self {
+        Self {
+            parameters: parameters.into(),
+            body,
+            env,
+        }
+    }
+
+    /// The environment that the function was created in.
+    pub const fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    pub fn apply(&self, args: &Cons, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+        self.apply_tail(args, env)?.finish()
     }
 
-    pub fn apply(&self, args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-        let (calling_args, env) = eval_list_elements(args, env)?;
-        self.body
-            .clone()
-            .eval(&join_two_lists_cons(&self.parameters, &calling_args, &env))
+    /// Evaluates and binds the arguments, then evaluates the body, except for
+    /// its last expression which is left for the caller. Fails without
+    /// evaluating anything if the number of arguments is wrong.
+    pub fn apply_tail(&self, args: &Cons, env: &Environment) -> Result<Evaluation> {
+        ensure_n_args("function", self.parameters.len(), args)?;
+        let parameters = self.parameters.clone();
+        let body = self.body.clone();
+        let captured_env = self.env.clone();
+        let env = env.clone();
+        Evaluation::then_all(args, &env.clone(), move |calling_args| {
+            let bindings = parameters.iter().cloned().zip(calling_args);
+            Ok(eval_body(&body, &captured_env.extend(bindings))?.returning_to(&env))
+        })
     }
 }
@@ -1,30 +1,160 @@
 use crate::{
-    functions::make_type_error,
-    types::{BuiltinFunction, Cons, Function, Quote, Symbol},
+    functions::{self, make_type_error},
+    span::EvalError,
+    types::{BuiltinFunction, Cons, Environment, Evaluation, Function, Quote, Rational, Symbol},
+};
+use anyhow::{bail, Error, Result};
+use num_bigint::BigInt;
+use std::{
+    fmt::{self, Write},
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
 };
-use anyhow::Result;
-use std::{fmt, rc::Rc};
 
 pub enum Object {
-    Integer(i32),
+    Integer(i64),
+    /// Only used for integers that do not fit in an `i64`, so that every
+    /// integer has exactly one representation and can be compared or hashed
+    /// structurally.
+    BigInteger(BigInt),
+    Rational(Rational),
+    Float(f64),
     Symbol(Symbol),
     Function(Function),
     BuiltinFunction(BuiltinFunction),
     Quote(Quote),
     Cons(Cons),
     Bool(bool),
+    String(String),
+    Char(char),
+}
+
+/// Writes `string` the way it would be written in source code.
+fn write_escaped(string: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '\r' => f.write_str("\\r")?,
+            '\0' => f.write_str("\\0")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", u32::from(c))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Writes `c` the way it would be written in source code.
+fn write_char_literal(c: char, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match c {
+        ' ' => f.write_str("#\\space"),
+        '\n' => f.write_str("#\\newline"),
+        '\t' => f.write_str("#\\tab"),
+        '\r' => f.write_str("#\\return"),
+        '\0' => f.write_str("#\\null"),
+        c if c.is_control() || c.is_whitespace() => write!(f, "#\\x{:x}", u32::from(c)),
+        c => write!(f, "#\\{c}"),
+    }
+}
+
+/// Writes `value` so that it reads back as a float, even if it has no
+/// fractional part.
+fn write_float(value: f64, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if value.is_nan() {
+        f.write_str("+nan.0")
+    } else if value.is_infinite() {
+        f.write_str(if value > 0.0 { "+inf.0" } else { "-inf.0" })
+    } else {
+        write!(f, "{value:?}")
+    }
 }
 
+/// Strings and characters are shown as they are, or written as literals when
+/// using the alternate flag (`{:#}`) so that the result can be read back in.
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Integer(inner) => inner.fmt(f),
+            Self::BigInteger(inner) => inner.fmt(f),
+            Self::Rational(inner) => inner.fmt(f),
+            Self::Float(inner) => write_float(*inner, f),
             Self::Symbol(inner) => inner.fmt(f),
             Self::Function(inner) => inner.fmt(f),
             Self::BuiltinFunction(inner) => inner.fmt(f),
             Self::Quote(inner) => inner.fmt(f),
             Self::Cons(inner) => inner.fmt(f),
             Self::Bool(inner) => inner.fmt(f),
+            Self::String(inner) if f.alternate() => write_escaped(inner, f),
+            Self::String(inner) => f.write_str(inner),
+            Self::Char(inner) if f.alternate() => write_char_literal(*inner, f),
+            Self::Char(inner) => f.write_char(*inner),
+        }
+    }
+}
+
+impl fmt::Debug for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:#}")
+    }
+}
+
+/// Structural equality, as used by `equal?`. Numbers are only equal if they
+/// are both exact or both inexact, and floats are compared by their bits so
+/// that NaN equals itself and `0.0` differs from `-0.0`.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(lhs), Self::Integer(rhs)) => lhs == rhs,
+            (Self::BigInteger(lhs), Self::BigInteger(rhs)) => lhs == rhs,
+            (Self::Rational(lhs), Self::Rational(rhs)) => lhs == rhs,
+            (Self::Float(lhs), Self::Float(rhs)) => lhs.to_bits() == rhs.to_bits(),
+            (Self::Symbol(lhs), Self::Symbol(rhs)) => lhs == rhs,
+            (Self::Function(lhs), Self::Function(rhs)) => lhs == rhs,
+            (Self::BuiltinFunction(lhs), Self::BuiltinFunction(rhs)) => lhs == rhs,
+            (Self::Quote(lhs), Self::Quote(rhs)) => lhs == rhs,
+            (Self::Cons(lhs), Self::Cons(rhs)) => lhs == rhs,
+            (Self::Bool(lhs), Self::Bool(rhs)) => lhs == rhs,
+            (Self::String(lhs), Self::String(rhs)) => lhs == rhs,
+            (Self::Char(lhs), Self::Char(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Object {}
+
+/// Hashes structurally, consistently with [`PartialEq`]. Nested lists and
+/// quotes are kept track of on the heap so that they can not overflow the
+/// stack.
+impl Hash for Object {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut pending = vec![self];
+        while let Some(obj) = pending.pop() {
+            mem::discriminant(obj).hash(state);
+            match obj {
+                Self::Integer(inner) => inner.hash(state),
+                Self::BigInteger(inner) => inner.hash(state),
+                Self::Rational(inner) => inner.hash(state),
+                Self::Float(inner) => inner.to_bits().hash(state),
+                Self::Symbol(inner) => inner.hash(state),
+                Self::Function(inner) => inner.hash(state),
+                Self::BuiltinFunction(inner) => inner.hash(state),
+                Self::Quote(Quote(inner)) => pending.push(inner),
+                Self::Cons(Cons(inner)) => {
+                    inner.is_some().hash(state);
+                    if let Some((first, rest)) = inner {
+                        pending.push(rest);
+                        pending.push(first);
+                    }
+                }
+                Self::Bool(inner) => inner.hash(state),
+                Self::String(inner) => inner.hash(state),
+                Self::Char(inner) => inner.hash(state),
+            }
         }
     }
 }
@@ -32,13 +162,17 @@ impl fmt::Display for Object {
 impl Object {
     pub const fn name_of_contained(&self) -> &str {
         match self {
-            Self::Integer(_) => "(type int)",
+            Self::Integer(_) | Self::BigInteger(_) => "(type int)",
+            Self::Rational(_) => "(type rational)",
+            Self::Float(_) => "(type float)",
             Self::Symbol(_) => "(type symbol)",
             Self::Function(_) => "(type function)",
             Self::BuiltinFunction(_) => "(type builtin-function)",
             Self::Quote(_) => "(type quote)",
             Self::Cons(_) => "(type cons)",
             Self::Bool(_) => "(type bool)",
+            Self::String(_) => "(type string)",
+            Self::Char(_) => "(type char)",
         }
     }
 
@@ -62,25 +196,167 @@ impl Object {
         }
     }
 
-    pub fn apply(&self, args: &Cons, env: &Cons) -> Result<(Rc<Self>, Cons)> {
+    pub fn apply(&self, args: &Cons, env: &Environment) -> Result<(Rc<Self>, Environment)> {
+        self.apply_tail(args, env)?.finish()
+    }
+
+    /// Like [`Self::apply`], but leaves the expression in tail position of
+    /// the function body for the caller to evaluate.
+    pub fn apply_tail(&self, args: &Cons, env: &Environment) -> Result<Evaluation> {
         match self {
-            Self::Function(func) => func.apply(args, env),
-            Self::BuiltinFunction(func) => func.apply(args, env),
+            Self::Function(func) => func.apply_tail(args, env),
+            Self::BuiltinFunction(func) => func.apply_tail(args, env),
             _ => Err(make_type_error("apply_obj", &[self])),
         }
     }
 
-    pub fn eval(self: Rc<Self>, env: &Cons) -> Result<(Rc<Self>, Cons)> {
+    pub fn eval(self: Rc<Self>, env: &Environment) -> Result<(Rc<Self>, Environment)> {
+        Evaluation::Tail {
+            expr: self,
+            env: env.clone(),
+            caller_env: None,
+        }
+        .finish()
+    }
+
+    /// Performs a single step of evaluation, see [`Evaluation`].
+    pub fn eval_tail(self: Rc<Self>, env: &Environment) -> Result<Evaluation> {
         match &*self {
-            Self::Integer(_) | Self::Bool(_) | Self::Function(_) | Self::BuiltinFunction(_) => {
-                Ok((self, env.clone()))
-            }
+            Self::Integer(_)
+            | Self::BigInteger(_)
+            | Self::Rational(_)
+            | Self::Float(_)
+            | Self::Bool(_)
+            | Self::String(_)
+            | Self::Char(_)
+            | Self::Function(_)
+            | Self::BuiltinFunction(_) => Ok(Evaluation::Done(self, env.clone())),
             Self::Cons(cons) => match &cons.0 {
-                None => Ok((self, env.clone())),
-                Some(_) => cons.eval(env),
+                None => Ok(Evaluation::Done(self, env.clone())),
+                Some(_) => cons
+                    .eval_tail(env)
+                    .map_err(|err| EvalError::add_expression(err, &self)),
             },
-            Self::Symbol(symbol) => symbol.eval(env),
-            Self::Quote(quote) => Ok((quote.0.clone(), env.clone())),
+            Self::Symbol(symbol) => symbol
+                .eval(env)
+                .map(Evaluation::from)
+                .map_err(|err| EvalError::add_expression(err, &self)),
+            Self::Quote(quote) => Ok(Evaluation::Done(quote.0.clone(), env.clone())),
+        }
+    }
+}
+
+impl From<i64> for Object {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<BigInt> for Object {
+    fn from(value: BigInt) -> Self {
+        functions::normalize_integer(value)
+    }
+}
+
+impl From<f64> for Object {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<i32> for Object {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<bool> for Object {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<String> for Object {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for Object {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<char> for Object {
+    fn from(value: char) -> Self {
+        Self::Char(value)
+    }
+}
+
+impl From<Symbol> for Object {
+    fn from(value: Symbol) -> Self {
+        Self::Symbol(value)
+    }
+}
+
+impl From<Cons> for Object {
+    fn from(value: Cons) -> Self {
+        Self::Cons(value)
+    }
+}
+
+impl TryFrom<&Object> for i64 {
+    type Error = Error;
+
+    fn try_from(value: &Object) -> Result<Self> {
+        match value {
+            Object::Integer(inner) => Ok(*inner),
+            Object::BigInteger(inner) => bail!("{inner} does not fit in an i64"),
+            _ => Err(make_type_error("i64::try_from", &[value])),
+        }
+    }
+}
+
+impl TryFrom<&Object> for BigInt {
+    type Error = Error;
+
+    fn try_from(value: &Object) -> Result<Self> {
+        match value {
+            Object::Integer(inner) => Ok((*inner).into()),
+            Object::BigInteger(inner) => Ok(inner.clone()),
+            _ => Err(make_type_error("BigInt::try_from", &[value])),
+        }
+    }
+}
+
+/// Integers are converted to the nearest float.
+impl TryFrom<&Object> for f64 {
+    type Error = Error;
+
+    fn try_from(value: &Object) -> Result<Self> {
+        functions::exact_to_inexact(value)
+    }
+}
+
+impl TryFrom<&Object> for i32 {
+    type Error = Error;
+
+    fn try_from(value: &Object) -> Result<Self> {
+        match value {
+            Object::Integer(inner) => Ok(Self::try_from(*inner)?),
+            _ => Err(make_type_error("i32::try_from", &[value])),
+        }
+    }
+}
+
+impl TryFrom<&Object> for bool {
+    type Error = Error;
+
+    fn try_from(value: &Object) -> Result<Self> {
+        match value {
+            Object::Bool(inner) => Ok(*inner),
+            _ => Err(make_type_error("bool::try_from", &[value])),
         }
     }
 }
@@ -1,10 +1,50 @@
-use crate::types::Object;
-use std::{fmt, rc::Rc};
+use crate::types::{Cons, Object};
+use std::{fmt, mem, rc::Rc};
 
 pub struct Quote(pub Rc<Object>);
 
+/// Frees deeply nested quotes without recursing, by handing the quoted object
+/// to a list, which takes apart whatever it contains on the heap.
+impl Drop for Quote {
+    fn drop(&mut self) {
+        let nested = matches!(&*self.0, Object::Quote(_) | Object::Cons(Cons(Some(_))));
+        if nested && Rc::strong_count(&self.0) == 1 {
+            let nil = || Rc::new(Object::Cons(Cons(None)));
+            drop(Cons(Some((mem::replace(&mut self.0, nil()), nil()))));
+        }
+    }
+}
+
+/// Quotes of quotes are unwrapped in a loop, and lists handle any quotes
+/// nested in them without recursing, so deeply nested quotes can be printed.
 impl fmt::Display for Quote {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "(quote {})", self.0)
+        let mut depth = 1;
+        let mut quoted = &self.0;
+        write!(f, "(quote ")?;
+        while let Object::Quote(Self(inner)) = &**quoted {
+            write!(f, "(quote ")?;
+            depth += 1;
+            quoted = inner;
+        }
+        quoted.fmt(f)?;
+        (0..depth).try_for_each(|_| write!(f, ")"))
     }
 }
+
+/// Compares quotes of quotes in a loop, for the same reason as
+/// [`fmt::Display`].
+impl PartialEq for Quote {
+    fn eq(&self, other: &Self) -> bool {
+        let (mut lhs, mut rhs) = (&self.0, &other.0);
+        while let (Object::Quote(Self(lhs_inner)), Object::Quote(Self(rhs_inner))) =
+            (&**lhs, &**rhs)
+        {
+            lhs = lhs_inner;
+            rhs = rhs_inner;
+        }
+        lhs == rhs
+    }
+}
+
+impl Eq for Quote {}
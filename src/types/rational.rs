@@ -0,0 +1,44 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::fmt;
+
+/// An exact fraction that is not an integer. It is always in lowest terms
+/// with a positive denominator, so every rational has exactly one
+/// representation.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: BigInt,
+    denominator: BigInt,
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+impl Rational {
+    /// Creates a rational without reducing it. The caller must make sure that
+    /// it is in lowest terms and that the denominator is greater than 1.
+    pub const fn new_unchecked(numerator: BigInt, denominator: BigInt) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    pub const fn numerator(&self) -> &BigInt {
+        &self.numerator
+    }
+
+    pub const fn denominator(&self) -> &BigInt {
+        &self.denominator
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match (self.numerator.to_f64(), self.denominator.to_f64()) {
+            (Some(numerator), Some(denominator)) => numerator / denominator,
+            _ => f64::NAN,
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use crate::types::{Cons, Object};
-use anyhow::{bail, Result};
+use crate::types::{Environment, Object};
+use anyhow::{anyhow, Result};
 use std::{fmt, rc::Rc, str::FromStr};
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Symbol {
     name: String,
 }
@@ -54,20 +54,19 @@ impl FromStr for Symbol {
 }
 
 impl Symbol {
-    pub fn eval(&self, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-        fn eval_symbol_internal(symbol: &Symbol, env: &Cons) -> Result<Rc<Object>> {
-            let Some((first, rest)) = &env.0 else {
-                bail!("Unbound variable {symbol}");
-            };
-            match &*first.clone().car()? {
-                Object::Symbol(found_symbol) if symbol == found_symbol => first.clone().cdr(),
-                _ => match &**rest {
-                    Object::Cons(next_cons) => eval_symbol_internal(symbol, next_cons),
-                    _ => bail!("Unbound variable {symbol}"),
-                },
-            }
-        }
+    /// Creates a symbol with any name, even one that `from_str` would reject.
+    pub const fn new_unchecked(name: String) -> Self {
+        Self { name }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 
-        Ok((eval_symbol_internal(self, env)?, env.clone()))
+    pub fn eval(&self, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+        let value = env
+            .get(self)
+            .ok_or_else(|| anyhow!("Unbound variable {self}"))?;
+        Ok((value, env.clone()))
     }
 }
@@ -1,177 +1,712 @@
-use crate::{functions, types::*};
-use anyhow::{bail, Result};
-use std::rc::Rc;
-
-macro_rules! wrap_fallible {
-    ($wrapped_name:ident, $unwrapped_name:expr) => {
-        pub fn $wrapped_name(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-            functions::ensure_n_args(stringify!($wrapped_name), 1, args)?;
-            let (first_arg, env) = args.car().eval(env)?;
-            Ok(($unwrapped_name(first_arg)?, env))
+use crate::{
+    functions,
+    native::{self, Rest},
+    types::{Cons, Environment, Evaluation, Function, Object, Quote, Symbol},
+};
+use anyhow::{bail, ensure, Result};
+use itertools::Itertools;
+use num_bigint::BigInt;
+use std::{cmp::Ordering, iter, rc::Rc, vec};
+
+macro_rules! wrap_native {
+    ($wrapped_name:ident, $native:expr) => {
+        pub fn $wrapped_name(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+            native::apply(name, Rc::new($native), args, env)
         }
     };
 }
 
-macro_rules! wrap_infallible {
-    ($wrapped_name:ident, $unwrapped_name:expr) => {
-        pub fn $wrapped_name(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-            functions::ensure_n_args(stringify!($wrapped_name), 1, args)?;
-            let (first_arg, env) = args.car().eval(env)?;
-            Ok(($unwrapped_name(first_arg), env))
+wrap_native!(car, |list: Rc<_>| {
+    if !matches!(&*list, Object::Cons(_)) {
+        return Err(functions::make_type_error("car", &[&list]));
+    }
+    list.car()
+});
+wrap_native!(cdr, |list: Rc<_>| {
+    if !matches!(&*list, Object::Cons(_)) {
+        return Err(functions::make_type_error("cdr", &[&list]));
+    }
+    list.cdr()
+});
+wrap_native!(cons, |car, cdr| -> Result<_> {
+    Ok(Object::Cons(Cons(Some((car, cdr)))))
+});
+wrap_native!(not, |obj: Rc<_>| -> Result<_> { Ok(functions::not(&obj)) });
+wrap_native!(int_to_bool, |obj: Rc<_>| functions::int_to_bool(&obj));
+wrap_native!(bool_to_int, |obj: Rc<_>| functions::bool_to_int(&obj));
+wrap_native!(is_nil, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::Cons(Cons(None))))
+});
+wrap_native!(is_int, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::Integer(_) | Object::BigInteger(_)))
+});
+wrap_native!(is_number, |obj: Rc<_>| -> Result<_> {
+    Ok(functions::is_number(&obj))
+});
+wrap_native!(is_integer, |obj: Rc<_>| -> Result<_> {
+    Ok(functions::is_integer(&obj))
+});
+wrap_native!(is_float, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::Float(_)))
+});
+wrap_native!(exact_to_inexact, |obj: Rc<_>| functions::exact_to_inexact(
+    &obj
+));
+wrap_native!(inexact_to_exact, |obj: Rc<_>| functions::inexact_to_exact(
+    &obj
+));
+wrap_native!(is_rational, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(
+        &*obj,
+        Object::Integer(_) | Object::BigInteger(_) | Object::Rational(_)
+    ))
+});
+wrap_native!(numerator, |obj: Rc<_>| functions::numerator(&obj));
+wrap_native!(denominator, |obj: Rc<_>| functions::denominator(&obj));
+wrap_native!(quotient, |lhs: Rc<_>, rhs: Rc<_>| functions::quotient(
+    &lhs, &rhs
+));
+wrap_native!(remainder, |lhs: Rc<_>, rhs: Rc<_>| functions::remainder(
+    &lhs, &rhs
+));
+wrap_native!(modulo, |lhs: Rc<_>, rhs: Rc<_>| functions::modulo(
+    &lhs, &rhs
+));
+wrap_native!(abs, |n: Rc<_>| functions::abs(&n));
+wrap_native!(min, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    if !functions::is_number(&first) {
+        return Err(functions::make_type_error("min", &[&first]));
+    }
+    rest.0
+        .iter()
+        .try_fold(first, |lhs, rhs| functions::min(&lhs, rhs))
+});
+wrap_native!(max, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    if !functions::is_number(&first) {
+        return Err(functions::make_type_error("max", &[&first]));
+    }
+    rest.0
+        .iter()
+        .try_fold(first, |lhs, rhs| functions::max(&lhs, rhs))
+});
+wrap_native!(gcd, |args: Rest<Rc<_>>| {
+    args.0
+        .iter()
+        .try_fold(Object::Integer(0), |lhs, rhs| functions::gcd(&lhs, rhs))
+});
+wrap_native!(lcm, |args: Rest<Rc<_>>| {
+    args.0
+        .iter()
+        .try_fold(Object::Integer(1), |lhs, rhs| functions::lcm(&lhs, rhs))
+});
+wrap_native!(expt, |base: Rc<_>, exponent: Rc<_>| functions::expt(
+    &base, &exponent
+));
+wrap_native!(exact_integer_sqrt, |n: BigInt| {
+    functions::exact_integer_sqrt(&n)
+});
+wrap_native!(bit_and, |args: Rest<Rc<_>>| {
+    args.0.iter().try_fold(Object::Integer(-1), |lhs, rhs| {
+        functions::bit_and(&lhs, rhs)
+    })
+});
+wrap_native!(bit_or, |args: Rest<Rc<_>>| {
+    args.0
+        .iter()
+        .try_fold(Object::Integer(0), |lhs, rhs| functions::bit_or(&lhs, rhs))
+});
+wrap_native!(bit_xor, |args: Rest<Rc<_>>| {
+    args.0
+        .iter()
+        .try_fold(Object::Integer(0), |lhs, rhs| functions::bit_xor(&lhs, rhs))
+});
+wrap_native!(arithmetic_shift, functions::arithmetic_shift);
+wrap_native!(num_eq, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    functions::compare_chain("=", &first, &rest.0, Ordering::is_eq)
+});
+wrap_native!(num_lt, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    functions::compare_chain("<", &first, &rest.0, Ordering::is_lt)
+});
+wrap_native!(num_gt, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    functions::compare_chain(">", &first, &rest.0, Ordering::is_gt)
+});
+wrap_native!(num_le, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    functions::compare_chain("<=", &first, &rest.0, Ordering::is_le)
+});
+wrap_native!(num_ge, |first: Rc<_>, rest: Rest<Rc<_>>| {
+    functions::compare_chain(">=", &first, &rest.0, Ordering::is_ge)
+});
+wrap_native!(is_zero, |n: Rc<_>| functions::sign_is(
+    "zero?",
+    &n,
+    Ordering::is_eq
+));
+wrap_native!(is_positive, |n: Rc<_>| functions::sign_is(
+    "positive?",
+    &n,
+    Ordering::is_gt
+));
+wrap_native!(is_negative, |n: Rc<_>| functions::sign_is(
+    "negative?",
+    &n,
+    Ordering::is_lt
+));
+wrap_native!(is_even, |n: Rc<_>| functions::is_even("even?", &n));
+wrap_native!(is_odd, |n: Rc<_>| -> Result<_> {
+    Ok(!functions::is_even("odd?", &n)?)
+});
+wrap_native!(is_eq, |lhs, rhs| -> Result<_> {
+    Ok(functions::is_eq(&lhs, &rhs))
+});
+wrap_native!(is_eqv, |lhs, rhs| -> Result<_> {
+    Ok(functions::is_eqv(&lhs, &rhs))
+});
+wrap_native!(is_equal, |lhs: Rc<Object>, rhs: Rc<Object>| -> Result<_> {
+    Ok(lhs == rhs)
+});
+wrap_native!(is_bool, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::Bool(_)))
+});
+wrap_native!(display, |obj: Rc<_>| -> Result<_> {
+    print!("{obj}");
+    Ok(Object::Cons(Cons(None)))
+});
+wrap_native!(is_string, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::String(_)))
+});
+wrap_native!(string_length, |string: String| functions::string_length(
+    &string
+));
+wrap_native!(substring, |string: String, start, end| {
+    functions::substring(&string, start, end)
+});
+wrap_native!(string_append, |strings: Rest<String>| -> Result<_> {
+    Ok(strings.0.concat())
+});
+wrap_native!(string_split, |string: String, separator: String| {
+    functions::string_split(&string, &separator)
+});
+wrap_native!(string_join, |strings: Cons, separator: String| {
+    functions::string_join(&strings, &separator)
+});
+wrap_native!(string_index_of, |string: String, needle: String| {
+    functions::string_index_of(&string, &needle)
+});
+wrap_native!(string_upcase, |string: String| -> Result<_> {
+    Ok(string.to_uppercase())
+});
+wrap_native!(string_downcase, |string: String| -> Result<_> {
+    Ok(string.to_lowercase())
+});
+wrap_native!(string_to_number, |string: String| -> Result<_> {
+    Ok(functions::string_to_number(&string))
+});
+wrap_native!(number_to_string, |number: Rc<_>| -> Result<_> {
+    match &*number {
+        _ if functions::is_number(&number) => Ok(number.to_string()),
+        _ => Err(functions::make_type_error("number->string", &[&number])),
+    }
+});
+wrap_native!(string_to_symbol, |string: String| -> Result<_> {
+    Ok(Symbol::new_unchecked(string))
+});
+wrap_native!(symbol_to_string, |symbol: Symbol| -> Result<_> {
+    Ok(symbol.to_string())
+});
+wrap_native!(is_char, |obj: Rc<_>| -> Result<_> {
+    Ok(matches!(&*obj, Object::Char(_)))
+});
+wrap_native!(is_char_alphabetic, |c: char| -> Result<_> {
+    Ok(c.is_alphabetic())
+});
+wrap_native!(is_char_numeric, |c: char| -> Result<_> {
+    Ok(c.is_numeric())
+});
+wrap_native!(is_char_whitespace, |c: char| -> Result<_> {
+    Ok(c.is_whitespace())
+});
+// Characters whose other case takes several characters, such as `ß`, are
+// left as they are.
+wrap_native!(char_upcase, |c: char| -> Result<_> {
+    Ok(c.to_uppercase().exactly_one().unwrap_or(c))
+});
+wrap_native!(char_downcase, |c: char| -> Result<_> {
+    Ok(c.to_lowercase().exactly_one().unwrap_or(c))
+});
+wrap_native!(char_to_integer, |c: char| -> Result<_> {
+    Ok(i64::from(u32::from(c)))
+});
+wrap_native!(integer_to_char, functions::integer_to_char);
+wrap_native!(char_eq, |first: char, rest: Rest<char>| -> Result<_> {
+    Ok(functions::is_chained(first, &rest.0, |lhs, rhs| lhs == rhs))
+});
+wrap_native!(char_lt, |first: char, rest: Rest<char>| -> Result<_> {
+    Ok(functions::is_chained(first, &rest.0, |lhs, rhs| lhs < rhs))
+});
+wrap_native!(char_gt, |first: char, rest: Rest<char>| -> Result<_> {
+    Ok(functions::is_chained(first, &rest.0, |lhs, rhs| lhs > rhs))
+});
+wrap_native!(char_le, |first: char, rest: Rest<char>| -> Result<_> {
+    Ok(functions::is_chained(first, &rest.0, |lhs, rhs| lhs <= rhs))
+});
+wrap_native!(char_ge, |first: char, rest: Rest<char>| -> Result<_> {
+    Ok(functions::is_chained(first, &rest.0, |lhs, rhs| lhs >= rhs))
+});
+wrap_native!(string_to_list, |string: String| -> Result<_> {
+    Ok(string
+        .chars()
+        .map(|c| Rc::new(Object::Char(c)))
+        .collect::<Cons>())
+});
+wrap_native!(list_to_string, |list: Cons| functions::list_to_string(
+    &list
+));
+
+pub fn newline(name: &str, args: &Cons, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+    functions::ensure_n_args(name, 0, args)?;
+    println!();
+    Ok((Rc::new(Object::Cons(Cons(None))), env.clone()))
+}
+
+pub fn quote(name: &str, args: &Cons, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+    functions::ensure_n_args(name, 1, args)?;
+    Ok((args.car(), env.clone()))
+}
+
+/// Evaluates the arguments of an arithmetic builtin, checks that they are all
+/// numbers and combines their values with `combine`.
+fn eval_numbers(
+    func_name: &str,
+    args: &Cons,
+    env: &Environment,
+    combine: impl FnOnce(&[Rc<Object>]) -> Result<Object> + 'static,
+) -> Result<Evaluation> {
+    ensure!(
+        args.is_proper_list(),
+        "arguments passed to {func_name} must be a proper list"
+    );
+    let func_name = Rc::<str>::from(func_name);
+    let env = env.clone();
+    Evaluation::then_all(args, &env.clone(), move |values| {
+        if !values.iter().all(|value| functions::is_number(value)) {
+            let values = values.iter().map(|value| &**value).collect::<Vec<_>>();
+            return Err(functions::make_type_error(&func_name, &values));
         }
+        Ok(Evaluation::Done(Rc::new(combine(&values)?), env))
+    })
+}
+
+pub fn add(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    eval_numbers(name, args, env, |values| {
+        values
+            .iter()
+            .try_fold(Object::Integer(0), |sum, value| functions::add(&sum, value))
+    })
+}
+
+pub fn sub(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    ensure!(
+        !args.is_empty(),
+        "{name} expected at least 1 argument but got 0"
+    );
+    eval_numbers(name, args, env, |values| match values {
+        [value] => functions::sub(&Object::Integer(0), value),
+        [first, second, rest @ ..] => rest
+            .iter()
+            .try_fold(functions::sub(first, second)?, |difference, value| {
+                functions::sub(&difference, value)
+            }),
+        [] => unreachable!("- takes at least 1 argument"),
+    })
+}
+
+pub fn mul(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    eval_numbers(name, args, env, |values| {
+        values
+            .iter()
+            .try_fold(Object::Integer(1), |product, value| {
+                functions::mul(&product, value)
+            })
+    })
+}
+
+pub fn div(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    ensure!(
+        !args.is_empty(),
+        "{name} expected at least 1 argument but got 0"
+    );
+    eval_numbers(name, args, env, |values| match values {
+        [value] => functions::div(&Object::Integer(1), value),
+        [first, second, rest @ ..] => rest
+            .iter()
+            .try_fold(functions::div(first, second)?, |quotient, value| {
+                functions::div(&quotient, value)
+            }),
+        [] => unreachable!("/ takes at least 1 argument"),
+    })
+}
+
+/// Creates a function from a list of parameters and the rest of a special
+/// form, which is its body.
+fn make_function(params: &Object, body: &Object, env: &Environment) -> Result<Function> {
+    let (Object::Cons(param_list), Object::Cons(body)) = (params, body) else {
+        bail!("first argument of lambda definition must be a list of parameters");
     };
+    ensure!(
+        param_list.is_proper_list(),
+        "parameters of a function must be a proper list"
+    );
+    let params = param_list
+        .iter()
+        .map(|param| match &*param {
+            Object::Symbol(name) => Ok(name.clone()),
+            _ => bail!("parameter of a function must be a symbol, but got {param}"),
+        })
+        .collect::<Result<_>>()?;
+    Ok(Function::new(params, body.clone(), env.clone()))
 }
 
-wrap_fallible!(car, Object::car);
-wrap_fallible!(cdr, Object::cdr);
-wrap_infallible!(not, |obj: Rc<_>| functions::not(&obj));
-wrap_fallible!(int_to_bool, |obj: Rc<_>| functions::int_to_bool(&obj));
-wrap_fallible!(bool_to_int, |obj: Rc<_>| functions::bool_to_int(&obj));
-wrap_infallible!(is_nil, |obj: Rc<_>| Rc::new(Object::Bool(matches!(
-    &*obj,
-    Object::Cons(Cons(None))
-))));
-wrap_infallible!(is_int, |obj: Rc<_>| Rc::new(Object::Bool(matches!(
-    &*obj,
-    Object::Integer(_)
-))));
-wrap_infallible!(is_bool, |obj: Rc<_>| Rc::new(Object::Bool(matches!(
-    &*obj,
-    Object::Bool(_)
-))));
-
-pub fn quote(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    functions::ensure_n_args("wrapped_quote", 1, args)?;
-    Ok((args.car(), env.clone()))
+pub fn lambda(name: &str, args: &Cons, env: &Environment) -> Result<(Rc<Object>, Environment)> {
+    functions::ensure_at_least_n_args(name, 2, args)?;
+    let func = make_function(&args.car(), &args.cdr(), env)?;
+    Ok((Rc::new(Object::Function(func)), env.clone()))
 }
 
-pub fn cons(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    functions::ensure_n_args("wrapped_cons", 2, args)?;
-    let (car, env) = args.car().eval(env)?;
-    let (cdr, env) = args.cdr().car()?.eval(&env)?;
-    Ok((Rc::new(Object::Cons(Cons(Some((car, cdr))))), env))
+pub fn begin(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 0, args)?;
+    functions::eval_body(args, env)
 }
 
-pub fn add(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    match &args.0 {
-        None => Ok((Rc::new(Object::Integer(0)), env.clone())),
-        Some((car, cdr)) => match &**cdr {
-            Object::Cons(rest) => {
-                let (lhs, env) = car.clone().eval(env)?;
-                let (rhs, env) = add(rest, &env)?;
-                Ok((Rc::new(functions::add(&lhs, &rhs)?), env))
-            }
-            _ => bail!("arguments passed to wrapped_add must be a proper list"),
-        },
+/// Evaluates `args` in order until the truthiness of one of them is
+/// `stop_at`, which is then the result. The last one is in tail position.
+fn eval_until(mut args: vec::IntoIter<Rc<Object>>, stop_at: bool, env: Environment) -> Evaluation {
+    let Some(arg) = args.next() else {
+        return Evaluation::Done(Rc::new(Object::Bool(!stop_at)), env);
+    };
+    if args.as_slice().is_empty() {
+        return Evaluation::tail_call(arg, &env);
     }
+    Evaluation::then(arg, &env.clone(), move |value, _| {
+        if functions::is_truthy(&value) == stop_at {
+            return Ok(Evaluation::Done(value, env));
+        }
+        Ok(eval_until(args, stop_at, env))
+    })
 }
 
-pub fn sub(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    match args.len() {
-        0 => bail!("wrapped_sub expected at least 1 argument but got 0"),
-        1 => {
-            let (rhs, env) = args.car().eval(env)?;
-            Ok((Rc::new(functions::sub(&Object::Integer(0), &rhs)?), env))
-        }
-        _ => match &*args.cdr() {
-            Object::Cons(rest) => {
-                let (lhs, env) = args.car().eval(env)?;
-                let (rhs, env) = add(rest, &env)?;
-                Ok((Rc::new(functions::sub(&lhs, &rhs)?), env))
-            }
-            _ => bail!("arguments passed to wrapped_sub must be a proper list"),
-        },
-    }
+/// Evaluates the arguments until one of them is false. The last one is in tail
+/// position.
+pub fn and(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 0, args)?;
+    let args = args.iter().collect::<Vec<_>>();
+    Ok(eval_until(args.into_iter(), false, env.clone()))
 }
 
-pub fn mul(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    match &args.0 {
-        None => Ok((Rc::new(Object::Integer(1)), env.clone())),
-        Some((car, cdr)) => match &**cdr {
-            Object::Cons(rest) => {
-                let (lhs, env) = car.clone().eval(env)?;
-                let (rhs, env) = mul(rest, &env)?;
-                Ok((Rc::new(functions::mul(&lhs, &rhs)?), env))
-            }
-            _ => bail!("arguments passed to wrapped_mul must be a proper list"),
-        },
-    }
+/// Evaluates the arguments until one of them is true. The last one is in tail
+/// position.
+pub fn or(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 0, args)?;
+    let args = args.iter().collect::<Vec<_>>();
+    Ok(eval_until(args.into_iter(), true, env.clone()))
 }
 
-pub fn lambda(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    functions::ensure_n_args("wrapped_lambda", 2, args)?;
+/// Binds `name` to `value` in the innermost frame of `env`.
+fn bind_definition(name: Symbol, value: Rc<Object>, env: &Environment) -> Evaluation {
+    env.define(name.clone(), value);
+    Evaluation::Done(Rc::new(Object::Symbol(name)), env.clone())
+}
+
+/// Binds either a variable, as in `(define x 1)`, or a function, as in
+/// `(define (f x) (* x 2))`. The binding is made in the innermost frame, so
+/// every closure that shares the frame sees it, including ones that were
+/// created before it.
+pub fn define(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
     match &*args.car() {
-        Object::Cons(param_list) => Ok((
-            Rc::new(Object::Function(Function::new(
-                param_list.clone(),
+        Object::Symbol(var_name) => {
+            functions::ensure_n_args(name, 2, args)?;
+            let (var_name, env) = (var_name.clone(), env.clone());
+            Ok(Evaluation::then(
                 args.cdr().car()?,
-            ))),
-            env.clone(),
-        )),
-        _ => bail!("first argument of lambda definition must be a list of parameters"),
+                &env.clone(),
+                move |var_value, _| Ok(bind_definition(var_name, var_value, &env)),
+            ))
+        }
+        Object::Cons(Cons(Some((func_name, params)))) => {
+            functions::ensure_at_least_n_args(name, 2, args)?;
+            let Object::Symbol(var_name) = &**func_name else {
+                bail!("name of function passed to define must be a symbol");
+            };
+            let func = make_function(params, &args.cdr(), env)?;
+            Ok(bind_definition(
+                var_name.clone(),
+                Rc::new(Object::Function(func)),
+                env,
+            ))
+        }
+        _ => bail!("first argument passed to define must be a symbol or a list"),
     }
 }
 
-pub fn and(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    match &args.0 {
-        None => Ok((Rc::new(Object::Bool(true)), env.clone())),
-        Some((car, cdr)) => match &**cdr {
-            Object::Cons(rest) => {
-                let (lhs, env) = car.clone().eval(env)?;
-                if functions::is_truthy(&lhs) {
-                    and(rest, &env)
-                } else {
-                    Ok((lhs, env))
+/// Splits the bindings of a `let`-like form, such as `((x 1) (y 2))`, into
+/// names and the expressions that give their values.
+fn parse_bindings(form_name: &str, bindings: &Object) -> Result<Vec<(Symbol, Rc<Object>)>> {
+    let Object::Cons(bindings) = bindings else {
+        bail!("bindings passed to {form_name} must be a list");
+    };
+    ensure!(
+        bindings.is_proper_list(),
+        "bindings passed to {form_name} must be a proper list"
+    );
+    bindings
+        .iter()
+        .map(|binding| match &*binding {
+            Object::Cons(binding) if binding.is_proper_list() && binding.len() == 2 => {
+                match &*binding.car() {
+                    Object::Symbol(name) => Ok((name.clone(), binding.cdr().car()?)),
+                    _ => bail!("name bound by {form_name} must be a symbol"),
                 }
             }
-            _ => bail!("arguments passed to wrapped_and must be a proper list"),
-        },
+            _ => bail!("binding passed to {form_name} must be a list of a name and a value"),
+        })
+        .collect()
+}
+
+/// Calls a function right away, as in
+/// `(let loop ((i 0)) (if (< i 10) (loop (+ i 1)) i))`. The function is only
+/// visible to itself.
+fn named_let(form_name: &str, name: &Symbol, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(form_name, 3, args)?;
+    let bindings = args.cdr().car()?;
+    let bindings = parse_bindings(form_name, &bindings)?;
+    let (params, inits): (Vec<_>, Vec<_>) = bindings
+        .into_iter()
+        .map(|(name, init)| (Rc::new(Object::Symbol(name)), init))
+        .unzip();
+    let params = Rc::new(Object::Cons(params.into_iter().collect()));
+    let loop_env = env.extend(iter::empty());
+    let func = Rc::new(Object::Function(make_function(
+        &params,
+        &Object::Cons(args.iter().skip(2).collect()),
+        &loop_env,
+    )?));
+    loop_env.define(name.clone(), func.clone());
+    func.apply_tail(&inits.into_iter().collect(), env)
+}
+
+/// Evaluates every value in the environment of the caller before binding any
+/// of them.
+pub fn r#let(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    if let Object::Symbol(loop_name) = &*args.car() {
+        return named_let(name, loop_name, args, env);
     }
+    functions::ensure_at_least_n_args(name, 2, args)?;
+    let (names, inits): (Vec<_>, Vec<_>) = parse_bindings(name, &args.car())?.into_iter().unzip();
+    let body = args.iter().skip(1).collect::<Cons>();
+    let env = env.clone();
+    Evaluation::then_all(inits, &env.clone(), move |values| {
+        let let_env = env.extend(names.into_iter().zip(values));
+        Ok(functions::eval_body(&body, &let_env)?.returning_to(&env))
+    })
 }
 
-pub fn or(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    match &args.0 {
-        None => Ok((Rc::new(Object::Bool(false)), env.clone())),
-        Some((car, cdr)) => match &**cdr {
-            Object::Cons(rest) => {
-                let (lhs, env) = car.clone().eval(env)?;
-                if functions::is_truthy(&lhs) {
-                    Ok((lhs, env))
-                } else {
-                    or(rest, &env)
-                }
-            }
-            _ => bail!("arguments passed to wrapped_or must be a proper list"),
+/// Evaluates each of `bindings` in turn in the environment that binding the
+/// previous ones with `bind` resulted in, and continues with `then` once all
+/// of them are bound.
+fn bind_in_order(
+    mut bindings: vec::IntoIter<(Symbol, Rc<Object>)>,
+    let_env: Environment,
+    bind: fn(&Environment, Symbol, Rc<Object>) -> Environment,
+    then: Box<dyn FnOnce(Environment) -> Result<Evaluation>>,
+) -> Result<Evaluation> {
+    let Some((name, init)) = bindings.next() else {
+        return then(let_env);
+    };
+    Ok(Evaluation::then(init, &let_env.clone(), move |value, _| {
+        bind_in_order(bindings, bind(&let_env, name, value), bind, then)
+    }))
+}
+
+/// Evaluates each value with the previous bindings already visible. Every
+/// binding gets a frame of its own, so a closure only sees the bindings that
+/// came before it.
+pub fn let_star(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 2, args)?;
+    let bindings = parse_bindings(name, &args.car())?;
+    let body = args.iter().skip(1).collect::<Cons>();
+    let env = env.clone();
+    bind_in_order(
+        bindings.into_iter(),
+        env.clone(),
+        |let_env, name, value| let_env.extend(iter::once((name, value))),
+        Box::new(move |let_env| Ok(functions::eval_body(&body, &let_env)?.returning_to(&env))),
+    )
+}
+
+/// Like `let*`, but every binding is made in the same frame, so functions
+/// bound by it can also see the bindings that come after them and can be
+/// mutually recursive. Used for both `letrec` and `letrec*`.
+pub fn letrec(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 2, args)?;
+    let bindings = parse_bindings(name, &args.car())?;
+    let body = args.iter().skip(1).collect::<Cons>();
+    let env = env.clone();
+    bind_in_order(
+        bindings.into_iter(),
+        env.extend(iter::empty()),
+        |let_env, name, value| {
+            let_env.define(name, value);
+            let_env.clone()
+        },
+        Box::new(move |let_env| Ok(functions::eval_body(&body, &let_env)?.returning_to(&env))),
+    )
+}
+
+/// Changes the value of the nearest binding of a variable, as in
+/// `(set! x 2)`, for every closure that can see it.
+pub fn set(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_n_args(name, 2, args)?;
+    let Object::Symbol(var_name) = &*args.car() else {
+        bail!("first argument passed to set! must be a symbol");
+    };
+    let (var_name, env) = (var_name.clone(), env.clone());
+    Ok(Evaluation::then(
+        args.cdr().car()?,
+        &env.clone(),
+        move |var_value, _| {
+            env.set(&var_name, var_value)?;
+            Ok(Evaluation::Done(Rc::new(Object::Symbol(var_name)), env))
         },
+    ))
+}
+
+fn is_keyword(obj: &Object, keyword: &str) -> bool {
+    matches!(obj, Object::Symbol(symbol) if symbol.name() == keyword)
+}
+
+/// Evaluates what comes after the test of a `cond` or `case` clause that was
+/// chosen. `value` is what the test evaluated to, which is passed to the
+/// function in a `(test => func)` clause.
+fn eval_clause(
+    form_name: &str,
+    value: Rc<Object>,
+    body: &Cons,
+    env: &Environment,
+) -> Result<Evaluation> {
+    if body.is_empty() {
+        return Ok(Evaluation::Done(value, env.clone()));
+    }
+    if is_keyword(&body.car(), "=>") {
+        ensure!(
+            body.len() == 2,
+            "`=>` in {form_name} must be followed by exactly one expression"
+        );
+        let env = env.clone();
+        return Ok(Evaluation::then(
+            body.cdr().car()?,
+            &env.clone(),
+            move |func, _| {
+                func.apply_tail(
+                    &iter::once(Rc::new(Object::Quote(Quote(value)))).collect(),
+                    &env,
+                )
+            },
+        ));
     }
+    Ok(functions::eval_body(body, env)?.returning_to(env))
 }
 
-pub fn define(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    functions::ensure_n_args("wrapped_define", 2, args)?;
-    match &*args.car() {
-        Object::Symbol(var_name) => {
-            let (var_value, env) = args.cdr().car()?.eval(env)?;
-            Ok((
-                Rc::new(Object::Symbol(var_name.clone())),
-                Cons(Some((
-                    Rc::new(Object::Cons(Cons(Some((
-                        Rc::new(Object::Symbol(var_name.clone())),
-                        var_value,
-                    ))))),
-                    Rc::new(Object::Cons(env)),
-                ))),
-            ))
+/// Splits a clause of a `cond` or `case` into its test and the rest.
+fn split_clause(form_name: &str, clause: &Object) -> Result<(Rc<Object>, Cons)> {
+    match clause {
+        Object::Cons(clause) if !clause.is_empty() && clause.is_proper_list() => {
+            Ok((clause.car(), clause.iter().skip(1).collect()))
         }
-        _ => bail!("first argument passed to define must be a symbol"),
+        _ => bail!("clause passed to {form_name} must be a non-empty list"),
     }
 }
 
-pub fn r#if(args: &Cons, env: &Cons) -> Result<(Rc<Object>, Cons)> {
-    functions::ensure_n_args("wrapped_if", 3, args)?;
-    let (condition, env) = args.car().eval(env)?;
-    if functions::is_truthy(&condition) {
-        args.cdr().car()?.eval(&env)
-    } else {
-        args.cdr().cdr()?.car()?.eval(&env)
+/// Tries each of `clauses` in turn until the test of one of them is true.
+fn eval_clauses(mut clauses: vec::IntoIter<Rc<Object>>, env: Environment) -> Result<Evaluation> {
+    let Some(clause) = clauses.next() else {
+        return Ok(Evaluation::Done(Rc::new(Object::Cons(Cons(None))), env));
+    };
+    let (test, body) = split_clause("cond", &clause)?;
+    if is_keyword(&test, "else") {
+        return eval_clause("cond", Rc::new(Object::Bool(true)), &body, &env);
     }
+    Ok(Evaluation::then(test, &env.clone(), move |value, _| {
+        if functions::is_truthy(&value) {
+            return eval_clause("cond", value, &body, &env);
+        }
+        eval_clauses(clauses, env)
+    }))
+}
+
+pub fn cond(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 0, args)?;
+    let clauses = args.iter().collect::<Vec<_>>();
+    eval_clauses(clauses.into_iter(), env.clone())
+}
+
+/// Picks the first clause that lists a datum that is `eqv?` to the key.
+pub fn case(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 1, args)?;
+    let clauses = args.iter().skip(1).collect::<Vec<_>>();
+    let env = env.clone();
+    Ok(Evaluation::then(args.car(), &env.clone(), move |key, _| {
+        for clause in clauses {
+            let (datums, body) = split_clause("case", &clause)?;
+            let matches = match &*datums {
+                _ if is_keyword(&datums, "else") => true,
+                Object::Cons(datums) => datums.iter().any(|datum| functions::is_eqv(&datum, &key)),
+                _ => bail!("datums in a case clause must be a list"),
+            };
+            if matches {
+                return eval_clause("case", key, &body, &env);
+            }
+        }
+        Ok(Evaluation::Done(Rc::new(Object::Cons(Cons(None))), env))
+    }))
+}
+
+/// Evaluates the body of a `when` or `unless` if the truthiness of its
+/// condition is `run_if`.
+fn eval_body_if(args: &Cons, env: &Environment, run_if: bool) -> Evaluation {
+    let body = args.iter().skip(1).collect::<Cons>();
+    let env = env.clone();
+    Evaluation::then(args.car(), &env.clone(), move |condition, _| {
+        if functions::is_truthy(&condition) != run_if {
+            return Ok(Evaluation::Done(Rc::new(Object::Cons(Cons(None))), env));
+        }
+        Ok(functions::eval_body(&body, &env)?.returning_to(&env))
+    })
+}
+
+pub fn when(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 1, args)?;
+    Ok(eval_body_if(args, env, true))
+}
+
+pub fn unless(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_at_least_n_args(name, 1, args)?;
+    Ok(eval_body_if(args, env, false))
+}
+
+pub fn r#if(name: &str, args: &Cons, env: &Environment) -> Result<Evaluation> {
+    functions::ensure_n_args(name, 3, args)?;
+    let consequent = args.cdr().car()?;
+    let alternative = args.cdr().cdr()?.car()?;
+    let env = env.clone();
+    Ok(Evaluation::then(
+        args.car(),
+        &env.clone(),
+        move |condition, _| {
+            let branch = if functions::is_truthy(&condition) {
+                consequent
+            } else {
+                alternative
+            };
+            Ok(Evaluation::tail_call(branch, &env))
+        },
+    ))
 }
@@ -0,0 +1,17 @@
+mod common;
+
+use common::eval;
+use rust_lisp::types::Object;
+
+#[test]
+fn case_is_changed() {
+    assert_eq!(*eval("(char-upcase #\\a)").unwrap(), Object::Char('A'));
+    assert_eq!(*eval("(char-downcase #\\Ä)").unwrap(), Object::Char('ä'));
+    assert_eq!(*eval("(char-upcase #\\1)").unwrap(), Object::Char('1'));
+}
+
+#[test]
+fn characters_without_a_single_character_case_are_kept() {
+    assert_eq!(*eval("(char-upcase #\\ß)").unwrap(), Object::Char('ß'));
+    assert_eq!(*eval("(char-downcase #\\İ)").unwrap(), Object::Char('İ'));
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::eval;
+use rust_lisp::types::Object;
+
+#[test]
+fn returned_closures_remember_their_environment() {
+    let result = eval(
+        "(define (make-adder n) (lambda (x) (+ x n)))
+         (define add-two (make-adder 2))
+         (define add-ten (make-adder 10))
+         (+ (add-two 1) (add-ten 1))",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(14));
+}
+
+#[test]
+fn closures_ignore_bindings_of_the_caller() {
+    let result = eval(
+        "(define n 1)
+         (define (get-n) n)
+         (define (shadow n) (get-n))
+         (shadow 2)",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(1));
+}
+
+#[test]
+fn parameters_shadow_outer_bindings() {
+    let result = eval(
+        "(define x 1)
+         (define (f x) (lambda () x))
+         ((f 2))",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(2));
+}
+
+#[test]
+fn closures_see_later_definitions() {
+    let result = eval(
+        "(define f (lambda () y))
+         (define y 5)
+         (f)",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(5));
+}
+
+#[test]
+fn top_level_functions_can_be_mutually_recursive() {
+    let result = eval(
+        "(define (my-even? n) (if (= n 0) true (my-odd? (- n 1))))
+         (define (my-odd? n) (if (= n 0) false (my-even? (- n 1))))
+         (my-even? 10)",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(true));
+}
+
+#[test]
+fn closures_sharing_a_binding_see_each_others_changes() {
+    let result = eval(
+        "(define (make-counter)
+           (define count 0)
+           (cons (lambda () (set! count (+ count 1)))
+                 (lambda () count)))
+         (define counter (make-counter))
+         ((car counter))
+         ((car counter))
+         ((cdr counter))",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(2));
+}
+
+#[test]
+fn setting_an_unbound_variable_is_an_error() {
+    let err = eval("(set! undefined-variable 1)").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Unbound variable undefined-variable"));
+}
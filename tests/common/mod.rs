@@ -0,0 +1,7 @@
+use rust_lisp::{types::Object, Interpreter};
+use std::rc::Rc;
+
+/// Evaluates `source` in a new interpreter.
+pub fn eval(source: &str) -> anyhow::Result<Rc<Object>> {
+    Interpreter::new().eval_str(source)
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use common::eval;
+use rust_lisp::{types::Object, Interpreter};
+
+#[test]
+fn internal_definitions_can_refer_to_each_other() {
+    let result = eval(
+        "(define (f)
+           (define (g) (h))
+           (define (h) 1)
+           (g))
+         (f)",
+    )
+    .unwrap();
+    assert_eq!(*result, Object::from(1));
+}
+
+#[test]
+fn internal_definitions_stay_inside_the_body() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(define (f) (define inner 1) inner) (f)")
+        .unwrap();
+    assert!(interpreter.eval_str("inner").is_err());
+}
+
+#[test]
+fn let_bodies_can_define() {
+    let result = eval("(let ((x 1)) (define y (+ x 1)) (* y 10))").unwrap();
+    assert_eq!(*result, Object::from(20));
+}
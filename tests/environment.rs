@@ -0,0 +1,115 @@
+use rust_lisp::{
+    types::{collect_cycles, BuiltinFunction, Cons, Environment, Object},
+    Interpreter,
+};
+use std::{cell::Cell, iter, rc::Rc};
+
+#[test]
+fn long_chains_of_frames_are_dropped() {
+    let mut env = Environment::new();
+    for i in 0..1_000_000 {
+        let name = format!("x{}", i % 10).parse().unwrap();
+        env = env.extend(iter::once((name, Rc::new(Object::from(i)))));
+    }
+    assert_eq!(
+        env.get(&"x9".parse().unwrap()).as_deref(),
+        Some(&Object::from(999_999))
+    );
+    drop(env);
+}
+
+#[test]
+fn frames_in_reference_cycles_are_freed() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(define (f x) (define (g) x) (g))")
+        .unwrap();
+    let payload = Rc::new(Object::from("payload"));
+    for _ in 0..100 {
+        interpreter.call_function("f", [payload.clone()]).unwrap();
+    }
+    collect_cycles();
+    assert_eq!(Rc::strong_count(&payload), 1);
+}
+
+#[test]
+fn frames_in_reference_cycles_are_freed_without_asking() {
+    let mut interpreter = Interpreter::new();
+    let payload = Rc::new(Object::from("payload"));
+    interpreter
+        .define_global("payload", payload.clone())
+        .unwrap();
+    interpreter
+        .eval_str(
+            "(define (f x) (define (g) x) (g))
+             (define (loop i) (when (< i 50000) (f payload) (loop (+ i 1))))
+             (loop 0)",
+        )
+        .unwrap();
+    assert!(Rc::strong_count(&payload) < 20_000);
+}
+
+#[test]
+fn frames_that_are_still_used_are_kept() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str(
+            "(define (make-counter)
+               (define n 0)
+               (define (next) (set! n (+ n 1)) n)
+               next)
+             (define counter (make-counter))
+             (counter)",
+        )
+        .unwrap();
+    // Only the host refers to this one.
+    let held = interpreter.eval_str("(make-counter)").unwrap();
+    collect_cycles();
+    assert_eq!(*interpreter.eval_str("(counter)").unwrap(), Object::from(2));
+    interpreter.define_global("held", held).unwrap();
+    assert_eq!(*interpreter.eval_str("(held)").unwrap(), Object::from(1));
+}
+
+#[test]
+fn frames_of_named_lets_are_freed() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .eval_str("(define (f x) (let loop ((i 0)) (if (< i 2) (loop (+ i 1)) x)))")
+        .unwrap();
+    let payload = Rc::new(Object::from("payload"));
+    for _ in 0..100 {
+        let result = interpreter.call_function("f", [payload.clone()]).unwrap();
+        assert!(Rc::ptr_eq(&result, &payload));
+    }
+    collect_cycles();
+    assert_eq!(Rc::strong_count(&payload), 1);
+}
+
+#[test]
+fn builtins_can_not_replace_the_global_environment() {
+    let mut interpreter = Interpreter::new();
+    let builtin = BuiltinFunction::new("fresh-env", |_, _| {
+        Ok((Rc::new(Object::Cons(Cons(None))), Environment::new()))
+    });
+    interpreter
+        .define_global("fresh-env", Object::BuiltinFunction(builtin))
+        .unwrap();
+    interpreter.eval_str("(define x 1) (fresh-env)").unwrap();
+    assert_eq!(*interpreter.eval_str("(+ x 1)").unwrap(), Object::from(2));
+}
+
+#[test]
+fn builtins_can_capture_host_state() {
+    let mut interpreter = Interpreter::new();
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    interpreter
+        .define_builtin("count!", move |_, _| {
+            counter.set(counter.get() + 1);
+            Ok(Rc::new(Object::from(counter.get())))
+        })
+        .unwrap();
+    let result = interpreter.eval_str("(count!) (count!)").unwrap();
+    assert_eq!(*result, Object::from(2));
+    assert_eq!(calls.get(), 2);
+}
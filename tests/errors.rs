@@ -0,0 +1,70 @@
+mod common;
+
+use common::eval;
+use rust_lisp::{types::Object, Interpreter};
+
+/// Whether the message of the error that evaluating `source` fails with
+/// starts with `name`, after the location.
+fn blames(source: &str, name: &str) -> bool {
+    let err = eval(source).unwrap_err().to_string();
+    err.contains(&format!(": {name} "))
+}
+
+#[test]
+fn builtins_are_named_as_registered() {
+    assert!(blames("(char-upcase 1)", "char-upcase"));
+    assert!(blames("(int->bool \"a\")", "int->bool"));
+    assert!(blames("(car 1 2)", "car"));
+    assert!(blames("(car 5)", "car"));
+    assert!(blames("(cdr 5)", "cdr"));
+}
+
+#[test]
+fn special_forms_are_named_as_registered() {
+    assert!(blames("(if 1 2)", "if"));
+    assert!(blames("(quote)", "quote"));
+    assert!(eval("(letrec* ((x)) x)")
+        .unwrap_err()
+        .to_string()
+        .contains("letrec*"));
+}
+
+#[test]
+fn arithmetic_is_named_as_registered() {
+    assert!(blames("(+ 1 \"a\")", "+"));
+    assert!(blames("(- 'a)", "-"));
+    assert!(blames("(/)", "/"));
+}
+
+#[test]
+fn builtins_defined_by_the_host_use_their_name() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .define_native("double", |n: i64| Ok(n * 2))
+        .unwrap();
+    let err = interpreter.eval_str("(double 'a)").unwrap_err();
+    assert!(err.to_string().contains(": double "));
+}
+
+#[test]
+fn functions_check_the_number_of_arguments() {
+    assert!(blames("((lambda (a b) b) 1)", "function"));
+    assert!(blames("((lambda (a) a) 1 2)", "function"));
+    assert!(blames("(define (f) 1) (f 1)", "function"));
+}
+
+#[test]
+fn wrong_argument_counts_are_reported_before_evaluating_arguments() {
+    let mut interpreter = Interpreter::new();
+    assert!(interpreter
+        .eval_str("(define x 0) ((lambda () 1) (set! x 1))")
+        .is_err());
+    assert_eq!(*interpreter.eval_str("x").unwrap(), Object::from(0));
+}
+
+#[test]
+fn parameters_must_be_symbols() {
+    assert!(eval("(lambda (1) 1)").is_err());
+    assert!(eval("(lambda (a . b) a)").is_err());
+    assert!(eval("(define (f x \"y\") x)").is_err());
+}
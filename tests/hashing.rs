@@ -0,0 +1,45 @@
+mod common;
+
+use common::eval;
+use rust_lisp::Interpreter;
+use std::hash::{BuildHasher, RandomState};
+
+#[test]
+fn equal_objects_hash_the_same() {
+    let hasher = RandomState::new();
+    for source in [
+        "'(1 (2.5 \"three\") #\\4 . five)",
+        "''(a b)",
+        "(expt 2 100)",
+        "(/ 1 3)",
+        "(/ 0.0 0.0)",
+    ] {
+        let (lhs, rhs) = (eval(source).unwrap(), eval(source).unwrap());
+        assert_eq!(lhs, rhs);
+        assert_eq!(hasher.hash_one(&lhs), hasher.hash_one(&rhs));
+    }
+}
+
+#[test]
+fn functions_hash_by_identity() {
+    let hasher = RandomState::new();
+    let mut interpreter = Interpreter::new();
+    let car = interpreter.eval_str("car").unwrap();
+    assert_eq!(
+        hasher.hash_one(&car),
+        hasher.hash_one(interpreter.eval_str("car").unwrap())
+    );
+    let func = interpreter.eval_str("(define (f) 1) f").unwrap();
+    assert_eq!(
+        hasher.hash_one(&func),
+        hasher.hash_one(interpreter.eval_str("f").unwrap())
+    );
+}
+
+#[test]
+fn long_lists_can_be_hashed() {
+    let list =
+        eval("(let loop ((i 0) (list '())) (if (= i 100000) list (loop (+ i 1) (cons i list))))")
+            .unwrap();
+    RandomState::new().hash_one(&list);
+}
@@ -0,0 +1,39 @@
+use rust_lisp::{
+    lexer::{lex, TokenKind},
+    parser::ParseErrorKind,
+    Interpreter,
+};
+
+fn kinds(source: &str) -> Vec<TokenKind> {
+    lex(source).into_iter().map(|token| token.kind).collect()
+}
+
+#[test]
+fn nested_block_comments_are_skipped() {
+    assert_eq!(
+        kinds("(#| a #| b |# c |# x)"),
+        [
+            TokenKind::LParen,
+            TokenKind::Ident("x".to_owned()),
+            TokenKind::RParen,
+        ]
+    );
+}
+
+#[test]
+fn unterminated_block_comments_are_malformed() {
+    for source in ["(x #| a", "#| a #| b |#", "#|"] {
+        assert_eq!(
+            kinds(source).last(),
+            Some(&TokenKind::Malformed(
+                ParseErrorKind::UnterminatedBlockComment
+            ))
+        );
+    }
+}
+
+#[test]
+fn unterminated_block_comments_are_parse_errors() {
+    let err = Interpreter::new().eval_str("1 #| 2").unwrap_err();
+    assert!(err.to_string().contains("unterminated block comment"));
+}
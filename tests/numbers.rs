@@ -0,0 +1,107 @@
+mod common;
+
+use common::eval;
+use rust_lisp::types::Object;
+
+#[test]
+fn huge_shifts_are_errors() {
+    let err = eval("(arithmetic-shift 1 4611686018427387904)").unwrap_err();
+    assert!(err.to_string().contains("too large"));
+    assert_eq!(
+        *eval("(arithmetic-shift -5 -4611686018427387904)").unwrap(),
+        Object::from(-1)
+    );
+}
+
+#[test]
+fn huge_powers_are_errors() {
+    let err = eval("(expt 2 4000000000)").unwrap_err();
+    assert!(err.to_string().contains("too large"));
+    assert_eq!(*eval("(expt -1 4000000001)").unwrap(), Object::from(-1));
+}
+
+#[test]
+fn powers_of_zero_and_one_allow_any_exponent() {
+    for (source, result) in [
+        ("(expt 1 5000000000)", 1),
+        ("(expt 1 -5000000000)", 1),
+        ("(expt -1 5000000000)", 1),
+        ("(expt -1 5000000001)", -1),
+        ("(expt -1 -5000000001)", -1),
+        ("(expt 0 5000000000)", 0),
+        ("(expt 0 0)", 1),
+    ] {
+        assert_eq!(*eval(source).unwrap(), Object::from(result));
+    }
+    let err = eval("(expt 0 -5000000000)").unwrap_err();
+    assert!(err.to_string().contains("division by zero"));
+}
+
+#[test]
+fn min_and_max_check_a_single_argument() {
+    assert!(eval("(min 'a)").is_err());
+    assert!(eval("(max \"x\")").is_err());
+    assert_eq!(*eval("(min 3)").unwrap(), Object::from(3));
+}
+
+#[test]
+fn float_literals_are_read() {
+    for (source, value) in [
+        ("+inf.0", f64::INFINITY),
+        ("-inf.0", f64::NEG_INFINITY),
+        ("1e3", 1000.0),
+        ("1.5e-3", 0.0015),
+        ("-2E2", -200.0),
+        (".5", 0.5),
+        ("1.", 1.0),
+    ] {
+        assert_eq!(*eval(source).unwrap(), Object::from(value));
+    }
+    for source in ["+nan.0", "-nan.0"] {
+        assert!(matches!(*eval(source).unwrap(), Object::Float(value) if value.is_nan()));
+    }
+    assert!(matches!(*eval("'inf").unwrap(), Object::Symbol(_)));
+}
+
+#[test]
+fn special_floats_are_printed_as_literals() {
+    assert_eq!(eval("(/ 1.0 0.0)").unwrap().to_string(), "+inf.0");
+    assert_eq!(eval("(- (/ 1.0 0.0))").unwrap().to_string(), "-inf.0");
+    assert_eq!(eval("(/ 0.0 0.0)").unwrap().to_string(), "+nan.0");
+    assert_eq!(eval("1e3").unwrap().to_string(), "1000.0");
+}
+
+#[test]
+fn comparisons_are_chained() {
+    for (source, result) in [
+        ("(< 1 2 3)", true),
+        ("(< 1 3 2)", false),
+        ("(<= 1 1 2)", true),
+        ("(> 3 2 2)", false),
+        ("(>= 3 2 2)", true),
+        ("(= 1 1 1.0)", true),
+        ("(= 1 1 2)", false),
+        ("(< 1/2 0.75 1)", true),
+        ("(< 1)", true),
+    ] {
+        assert_eq!(*eval(source).unwrap(), Object::from(result));
+    }
+}
+
+#[test]
+fn chained_comparisons_stop_at_the_first_false_pair() {
+    assert_eq!(*eval("(< 2 1 'a)").unwrap(), Object::from(false));
+    assert!(eval("(< 1 2 'a)").is_err());
+}
+
+#[test]
+fn strings_with_a_zero_denominator_are_not_numbers() {
+    assert_eq!(
+        *eval("(string->number \"1/0\")").unwrap(),
+        Object::from(false)
+    );
+    assert_eq!(
+        *eval("(string->number \"2/4\")").unwrap(),
+        *eval("1/2").unwrap()
+    );
+}
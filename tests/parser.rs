@@ -0,0 +1,104 @@
+use rust_lisp::{
+    parser::{ParseErrorKind, ParseErrors},
+    Interpreter,
+};
+
+fn parse_errors(source: &str) -> ParseErrors {
+    Interpreter::new()
+        .parse("test.lisp", source)
+        .unwrap_err()
+        .downcast()
+        .unwrap()
+}
+
+fn kinds(source: &str) -> Vec<ParseErrorKind> {
+    parse_errors(source)
+        .errors()
+        .map(|error| error.kind.clone())
+        .collect()
+}
+
+#[test]
+fn every_kind_of_error_is_reported() {
+    for (source, kind) in [
+        ("(a b", ParseErrorKind::UnclosedParen),
+        ("a)", ParseErrorKind::StrayRParen),
+        ("(a . b c)", ParseErrorKind::MisplacedDot),
+        ("(a ')", ParseErrorKind::DanglingQuote),
+        ("(a #;)", ParseErrorKind::DanglingDatumComment),
+        ("1a", ParseErrorKind::InvalidIdentifier("1a".to_owned())),
+        ("\"abc", ParseErrorKind::UnterminatedString),
+        ("#| a", ParseErrorKind::UnterminatedBlockComment),
+        ("\"\\q\"", ParseErrorKind::InvalidEscape("\\q".to_owned())),
+        (
+            "#\\nope",
+            ParseErrorKind::InvalidCharacter("nope".to_owned()),
+        ),
+        ("1/0", ParseErrorKind::DivisionByZero("1/0".to_owned())),
+    ] {
+        assert_eq!(kinds(source), [kind]);
+    }
+}
+
+#[test]
+fn several_errors_are_reported_at_once() {
+    assert_eq!(
+        kinds("(a) b) (c . d e) 1a"),
+        [
+            ParseErrorKind::StrayRParen,
+            ParseErrorKind::MisplacedDot,
+            ParseErrorKind::InvalidIdentifier("1a".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn errors_point_at_their_line() {
+    assert_eq!(
+        parse_errors("(a . b c) #;").to_string(),
+        "test.lisp:1:4: misplaced `.`
+1 | (a . b c) #;
+  |    ^
+test.lisp:1:11: `#;` is not followed by an expression
+1 | (a . b c) #;
+  |           ^^"
+    );
+    assert_eq!(
+        parse_errors(&format!("{}  1a", "\n".repeat(9))).to_string(),
+        "test.lisp:10:3: invalid identifier `1a`
+10 |   1a
+   |   ^^"
+    );
+}
+
+#[test]
+fn carets_are_as_wide_as_the_characters_on_the_line() {
+    assert_eq!(
+        parse_errors("\"héllo").to_string(),
+        "test.lisp:1:1: unterminated string
+1 | \"héllo
+  | ^^^^^^"
+    );
+    assert_eq!(
+        parse_errors("(ü \"ab\ncd").to_string(),
+        "test.lisp:1:4: unterminated string
+1 | (ü \"ab
+  |    ^^^"
+    );
+    assert_eq!(
+        parse_errors("(a\n  ö))").to_string(),
+        "test.lisp:2:5: unexpected `)`
+2 |   ö))
+  |     ^"
+    );
+}
+
+#[test]
+fn rational_literals_with_a_zero_denominator_are_errors() {
+    assert_eq!(
+        parse_errors("(+ 1 -3/0)").to_string(),
+        "test.lisp:1:6: division by zero in `-3/0`
+1 | (+ 1 -3/0)
+  |      ^^^^"
+    );
+}
@@ -0,0 +1,59 @@
+use rust_lisp::{types::Object, Interpreter};
+
+#[test]
+fn deeply_nested_lists_can_be_parsed() {
+    let depth = 100_000;
+    let source = format!("(car '{}{})", "(".repeat(depth), ")".repeat(depth));
+    let result = Interpreter::new().eval_str(&source).unwrap();
+    assert!(matches!(&*result, Object::Cons(_)));
+}
+
+#[test]
+fn deeply_nested_quotes_can_be_printed_compared_and_dropped() {
+    let depth = 100_000;
+    let nested = format!("'{}x{}", "('".repeat(depth), ")".repeat(depth));
+    let mut interpreter = Interpreter::new();
+    let source = format!("(define a {nested}) (define b {nested}) (equal? a b)");
+    assert_eq!(*interpreter.eval_str(&source).unwrap(), Object::from(true));
+    let printed = interpreter.eval_str("a").unwrap().to_string();
+    assert!(printed.starts_with("((quote ((quote (("));
+    assert_eq!(printed.len(), "((quote ))".len() * depth + 1);
+
+    let quotes = format!("'{}x", "'".repeat(depth));
+    let source = format!("(define c {quotes}) (define d {quotes}) (equal? c d)");
+    assert_eq!(*interpreter.eval_str(&source).unwrap(), Object::from(true));
+    let printed = interpreter.eval_str("c").unwrap().to_string();
+    assert_eq!(printed.len(), "(quote )".len() * depth + 1);
+}
+
+#[test]
+fn deep_recursion_is_an_error() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_depth(1000);
+    let err = interpreter
+        .eval_str("(define (f n) (+ 1 (f n))) (f 0)")
+        .unwrap_err();
+    assert!(err.to_string().contains("stack depth exceeded"));
+}
+
+#[test]
+fn tail_calls_do_not_count_towards_the_depth() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_max_depth(1000);
+    let source = "
+        (define (loop i)
+          'ignored
+          (if (= i 1000000)
+              i
+              (cond ((< i 0) 'never)
+                    (else (and true
+                               (or false
+                                   (when true
+                                     'ignored
+                                     (loop (+ i 1)))))))))
+        (loop 0)";
+    assert_eq!(
+        *interpreter.eval_str(source).unwrap(),
+        Object::from(1_000_000)
+    );
+}
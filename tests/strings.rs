@@ -0,0 +1,54 @@
+mod common;
+
+use common::eval;
+use rust_lisp::types::Object;
+
+#[test]
+fn escape_sequences_are_read() {
+    assert_eq!(
+        *eval(r#""a\n\t\r\0\"\\\u{e9}""#).unwrap(),
+        Object::from("a\n\t\r\0\"\\é")
+    );
+}
+
+#[test]
+fn substrings_count_characters() {
+    assert_eq!(
+        *eval(r#"(substring "héllo wörld" 1 8)"#).unwrap(),
+        Object::from("éllo wö")
+    );
+    assert_eq!(
+        *eval(r#"(string-length "wörld")"#).unwrap(),
+        Object::from(5)
+    );
+    assert_eq!(
+        *eval(r#"(string-index-of "héllo" "l")"#).unwrap(),
+        Object::from(2)
+    );
+    let err = eval(r#"(substring "héllo" 1 6)"#).unwrap_err();
+    assert!(err.to_string().contains("out of range"));
+}
+
+#[test]
+fn strings_are_split_and_joined() {
+    assert_eq!(
+        *eval(r#"(string-split "a,b,,c" ",")"#).unwrap(),
+        *eval(r#"'("a" "b" "" "c")"#).unwrap()
+    );
+    assert_eq!(
+        *eval(r#"(string-join (string-split "a,b,,c" ",") "-")"#).unwrap(),
+        Object::from("a-b--c")
+    );
+    assert!(eval(r#"(string-split "a" "")"#).is_err());
+    assert!(eval(r#"(string-join '("a" 1) ",")"#).is_err());
+}
+
+#[test]
+fn strings_are_printed_as_literals_with_the_alternate_flag() {
+    let string = eval(r#""a\n\"b\"\u{1}""#).unwrap();
+    assert_eq!(string.to_string(), "a\n\"b\"\u{1}");
+    assert_eq!(format!("{string:#}"), r#""a\n\"b\"\u{1}""#);
+    let list = eval(r#"'("x y" #\space "é")"#).unwrap();
+    assert_eq!(list.to_string(), "(x y   é)");
+    assert_eq!(format!("{list:#}"), r#"("x y" #\space "é")"#);
+}